@@ -0,0 +1,104 @@
+use glam::{vec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Particle;
+
+/// How the edges of the unit-square world behave.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Boundary {
+    /// Open plane with a gentle pull back toward the centre
+    CentrePull,
+    /// Toroidal world: leaving one edge re-enters from the opposite one
+    Wrap,
+    /// Particles bounce off the walls
+    Reflect,
+    /// Infinite plane with nothing holding particles in
+    Open,
+}
+
+impl Boundary {
+    pub fn next(self) -> Self {
+        match self {
+            Boundary::CentrePull => Boundary::Wrap,
+            Boundary::Wrap => Boundary::Reflect,
+            Boundary::Reflect => Boundary::Open,
+            Boundary::Open => Boundary::CentrePull,
+        }
+    }
+
+    /// Vector from `from` to `to`, taking the shortest route across a wrapped edge.
+    pub fn offset(self, from: Vec2, to: Vec2) -> Vec2 {
+        let delta = to - from;
+        match self {
+            Boundary::Wrap => delta - delta.round(),
+            _ => delta,
+        }
+    }
+
+    /// Copies of `point` shifted by whole world widths that lie within `radius`
+    /// of the unit square, so neighbour queries see across wrapped edges.
+    pub fn images(self, point: Vec2, radius: f32) -> Vec<Vec2> {
+        if self != Boundary::Wrap {
+            return vec![point];
+        }
+        let shifts = |v: f32| {
+            let mut s = vec![0.];
+            if v - radius < 0. {
+                s.push(1.);
+            }
+            if v + radius >= 1. {
+                s.push(-1.);
+            }
+            s
+        };
+        let mut images = Vec::new();
+        for dx in shifts(point.x) {
+            for dy in shifts(point.y) {
+                images.push(point + vec2(dx, dy));
+            }
+        }
+        images
+    }
+
+    /// Acceleration back toward the centre of the world, if this boundary has
+    /// one.
+    pub fn pull(self, position: Vec2, strength: f32) -> Vec2 {
+        if self == Boundary::CentrePull {
+            -(position - vec2(0.5, 0.5)) * strength
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    /// Bring a particle back inside the world after it has moved.
+    pub fn confine(self, particle: &mut Particle) {
+        match self {
+            Boundary::Wrap => {
+                particle.position = particle.position - particle.position.floor();
+                // `x - floor(x)` can round up to exactly 1 for tiny negative x
+                if particle.position.x >= 1. {
+                    particle.position.x = 0.;
+                }
+                if particle.position.y >= 1. {
+                    particle.position.y = 0.;
+                }
+            }
+            Boundary::Reflect => {
+                for axis in 0..2 {
+                    if particle.position[axis] < 0. {
+                        particle.position[axis] = -particle.position[axis];
+                        particle.velocity[axis] = -particle.velocity[axis];
+                    } else if particle.position[axis] > 1. {
+                        particle.position[axis] = 2. - particle.position[axis];
+                        particle.velocity[axis] = -particle.velocity[axis];
+                    }
+                }
+                // A particle moving more than a world width per step could
+                // still be outside
+                particle.position = particle.position.clamp(Vec2::ZERO, Vec2::ONE);
+            }
+            Boundary::CentrePull | Boundary::Open => {}
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use macroquad::prelude::*;
+use particle_life::render::View;
+use particle_life::Particle;
+
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 50.;
+// Radius (in window heights) of the region whose centre of mass is followed
+const FOLLOW_RADIUS: f32 = 0.2;
+const FOLLOW_RATE: f32 = 0.1;
+// How close (in pixels) the cursor must be to a particle to pick it
+const PICK_DISTANCE: f32 = 12.;
+
+// Maps world coordinates onto the window. At zoom 1 the unit square fills the
+// shorter side of the window, centred on `centre`.
+pub struct Camera {
+    pub centre: Vec2,
+    pub zoom: f32,
+    pub follow: bool,
+    // Id of the particle kept in the centre of the view, if any
+    pub tracking: Option<u32>,
+    drag_from: Option<Vec2>,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            centre: vec2(0.5, 0.5),
+            zoom: 1.,
+            follow: false,
+            tracking: None,
+            drag_from: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Camera::new();
+    }
+
+    fn scale(&self) -> f32 {
+        self.zoom * screen_width().min(screen_height())
+    }
+
+    fn screen_centre() -> Vec2 {
+        vec2(screen_width(), screen_height()) / 2.
+    }
+
+    pub fn world_to_screen(&self, world: Vec2) -> Vec2 {
+        (world - self.centre) * self.scale() + Camera::screen_centre()
+    }
+
+    pub fn screen_to_world(&self, screen: Vec2) -> Vec2 {
+        (screen - Camera::screen_centre()) / self.scale() + self.centre
+    }
+
+    // A distance on screen, in world units
+    pub fn world_length(&self, pixels: f32) -> f32 {
+        pixels / self.scale()
+    }
+
+    // Right-drag pans, scrolling zooms around the cursor
+    pub fn handle_input(&mut self) {
+        let mouse = Vec2::from(mouse_position());
+        if is_mouse_button_down(MouseButton::Right) {
+            if let Some(from) = self.drag_from {
+                self.centre -= (mouse - from) / self.scale();
+                self.follow = false;
+                self.tracking = None;
+            }
+            self.drag_from = Some(mouse);
+        } else {
+            self.drag_from = None;
+        }
+
+        let (_, scroll) = mouse_wheel();
+        if scroll != 0. {
+            let anchor = self.screen_to_world(mouse);
+            let factor = if scroll > 0. {
+                ZOOM_STEP
+            } else {
+                1. / ZOOM_STEP
+            };
+            self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+            // Keep the world point under the cursor fixed
+            self.centre = anchor - (mouse - Camera::screen_centre()) / self.scale();
+        }
+    }
+
+    // Drift toward the centre of mass of the particles around the view centre,
+    // which keeps a moving cluster in frame
+    pub fn update_follow(&mut self, population: &[Particle]) {
+        if let Some(id) = self.tracking {
+            match population.iter().find(|p| p.id == id) {
+                Some(p) => self.centre = p.position,
+                None => self.tracking = None,
+            }
+            return;
+        }
+        if !self.follow {
+            return;
+        }
+        let radius = FOLLOW_RADIUS / self.zoom;
+        let (sum, count) = population
+            .iter()
+            .filter(|p| p.position.distance(self.centre) < radius)
+            .fold((Vec2::ZERO, 0), |(sum, count), p| {
+                (sum + p.position, count + 1)
+            });
+        if count > 0 {
+            let target = sum / count as f32;
+            self.centre = self.centre.lerp(target, FOLLOW_RATE);
+        }
+    }
+
+    // Id of the particle drawn nearest to `screen`, if one is close enough
+    pub fn pick(&self, population: &[Particle], screen: Vec2) -> Option<u32> {
+        let world = self.screen_to_world(screen);
+        let max_distance = (PICK_DISTANCE + self.particle_size()) / self.scale();
+        population
+            .iter()
+            .map(|p| (p.id, p.position.distance(world)))
+            .filter(|(_, distance)| *distance < max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+    // The same framing for the offscreen renderer
+    pub fn view(&self) -> View {
+        View {
+            centre: self.centre,
+            zoom: self.zoom,
+        }
+    }
+
+    // Particle radius in pixels, growing with zoom but never vanishing
+    pub fn particle_size(&self) -> f32 {
+        (2. * self.zoom).max(1.)
+    }
+}
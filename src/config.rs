@@ -0,0 +1,378 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::boundary::Boundary;
+use crate::field::ForceField;
+use crate::integrator::Integrator;
+use crate::kernel::KernelKind;
+use crate::matrix::AttractionMatrix;
+use crate::neighbours::NeighbourSearch;
+use crate::palette::{self, MAX_SPECIES, MIN_SPECIES};
+use crate::simulation::{Params, Simulation};
+use crate::spawn::{DensityMap, Layout};
+use crate::species::{self, Species};
+
+// Species count for an `hsv` palette without `num_species`
+const DEFAULT_SPECIES: usize = 5;
+
+/// Everything that can be set from a config file, before it is turned into
+/// simulation `Params` and a colour palette.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub seed: u64,
+    pub max_radius: f32,
+    pub time_step: f32,
+    pub friction_half_life: f32,
+    pub beta: f32,
+    pub num_particles: usize,
+    pub search: NeighbourSearch,
+    pub boundary: Boundary,
+    pub centre_pull: f32,
+    pub kernel: KernelKind,
+    pub integrator: Integrator,
+    pub layout: Layout,
+    /// The force field tool, as a `[field]` table
+    pub field: ForceField,
+    /// Image whose brightness gives the particle density for `layout = "image"`
+    pub layout_image: Option<PathBuf>,
+    /// Split steps so no particle moves further than this in one
+    pub max_displacement: Option<f32>,
+    /// Row-major per-pair repulsion distances, one per (from, to) colour pair
+    pub pair_betas: Option<Vec<f32>>,
+    /// Draw per-pair repulsion distances from this [low, high) range instead
+    pub pair_beta_range: Option<[f32; 2]>,
+    /// Row-major per-pair interaction radii, one per (from, to) colour pair
+    pub pair_radii: Option<Vec<f32>>,
+    /// Draw per-pair interaction radii from this [low, high) range instead
+    pub pair_radius_range: Option<[f32; 2]>,
+    /// Explicit colour list, one species per colour
+    pub colors: Option<Vec<String>>,
+    /// A named preset from `palette::PRESETS`, used when `colors` is not set
+    pub palette: Option<String>,
+    /// Number of species, taken from the palette when not set
+    pub num_species: Option<usize>,
+    /// Optional per-colour overrides, one `[[species]]` table per colour
+    pub species: Vec<Species>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let params = Params::default();
+        Config {
+            seed: params.seed,
+            max_radius: params.max_radius,
+            time_step: params.time_step,
+            friction_half_life: params.friction_half_life,
+            beta: params.beta,
+            num_particles: params.num_particles,
+            search: params.search,
+            boundary: params.boundary,
+            centre_pull: params.centre_pull,
+            kernel: params.kernel,
+            integrator: params.integrator,
+            layout: params.layout,
+            field: params.field,
+            layout_image: None,
+            max_displacement: params.max_displacement,
+            pair_betas: None,
+            pair_beta_range: params.pair_beta_range,
+            pair_radii: None,
+            pair_radius_range: params.pair_radius_range,
+            species: Vec::new(),
+            colors: None,
+            palette: None,
+            num_species: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Write(PathBuf, String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "could not parse {}: {}", path.display(), e),
+            ConfigError::Write(path, e) => write!(f, "could not write {}: {}", path.display(), e),
+            ConfigError::Invalid(msg) => write!(f, "invalid config: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Read a TOML config file. Missing keys fall back to the defaults.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Write this config as TOML, in the same form `load` reads.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let write_err =
+            |e: &dyn fmt::Display| ConfigError::Write(path.to_path_buf(), e.to_string());
+        let mut value = toml::Value::try_from(self).map_err(|e| write_err(&e))?;
+        shorten_floats(&mut value);
+        let text = toml::to_string(&value).map_err(|e| write_err(&e))?;
+        std::fs::write(path, text).map_err(|e| write_err(&e))
+    }
+
+    /// Copy the simulation constants back from `params`, e.g. after they were
+    /// changed at runtime.
+    pub fn set_params(&mut self, params: &Params) {
+        self.seed = params.seed;
+        self.max_radius = params.max_radius;
+        self.time_step = params.time_step;
+        self.friction_half_life = params.friction_half_life;
+        self.beta = params.beta;
+        // Erasing with the brush can leave nothing, which a config cannot ask for
+        self.num_particles = params.num_particles.max(1);
+        self.search = params.search;
+        self.boundary = params.boundary;
+        self.centre_pull = params.centre_pull;
+        self.kernel = params.kernel;
+        self.integrator = params.integrator;
+        self.layout = params.layout;
+        self.field = params.field;
+        self.max_displacement = params.max_displacement;
+        self.pair_radius_range = params.pair_radius_range;
+        self.pair_beta_range = params.pair_beta_range;
+    }
+
+    /// Check every value is in range, so a bad config fails at start-up rather
+    /// than producing NaNs mid-run.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.params().check().map_err(ConfigError::Invalid)?;
+        if self.num_particles == 0 {
+            return Err(ConfigError::Invalid(
+                "num_particles must be at least 1".to_string(),
+            ));
+        }
+        self.palette()?;
+        self.pair_betas()?;
+        self.pair_radii()?;
+        if self.pair_betas.is_some() && self.pair_beta_range.is_some() {
+            return Err(ConfigError::Invalid(
+                "set either pair_betas or pair_beta_range, not both".to_string(),
+            ));
+        }
+        if self.pair_radii.is_some() && self.pair_radius_range.is_some() {
+            return Err(ConfigError::Invalid(
+                "set either pair_radii or pair_radius_range, not both".to_string(),
+            ));
+        }
+        let count = self.species_count()?;
+        if !self.species.is_empty() && self.species.len() != count {
+            return Err(ConfigError::Invalid(format!(
+                "{} [[species]] tables given for {} species; list one per species or none",
+                self.species.len(),
+                count
+            )));
+        }
+        species::check_all(&self.species).map_err(ConfigError::Invalid)?;
+        if self.layout == Layout::Image && self.layout_image.is_none() {
+            return Err(ConfigError::Invalid(
+                "layout = \"image\" needs a layout_image".to_string(),
+            ));
+        }
+        self.density()?;
+        Ok(())
+    }
+
+    pub fn params(&self) -> Params {
+        Params {
+            seed: self.seed,
+            max_radius: self.max_radius,
+            time_step: self.time_step,
+            friction_half_life: self.friction_half_life,
+            beta: self.beta,
+            num_particles: self.num_particles,
+            search: self.search,
+            boundary: self.boundary,
+            centre_pull: self.centre_pull,
+            kernel: self.kernel,
+            integrator: self.integrator,
+            layout: self.layout,
+            field: self.field,
+            max_displacement: self.max_displacement,
+            pair_radius_range: self.pair_radius_range,
+            pair_beta_range: self.pair_beta_range,
+        }
+    }
+
+    /// A fresh simulation with everything from this config applied.
+    pub fn simulation(&self) -> Result<Simulation, ConfigError> {
+        let mut sim = Simulation::with_species(self.params(), self.species()?, self.density()?);
+        if let Some(betas) = self.pair_betas()? {
+            sim.betas = Some(betas);
+        }
+        if let Some(radii) = self.pair_radii()? {
+            sim.radii = Some(radii);
+        }
+        Ok(sim)
+    }
+
+    pub fn species(&self) -> Result<Vec<Species>, ConfigError> {
+        if self.species.is_empty() {
+            Ok(vec![Species::default(); self.species_count()?])
+        } else {
+            Ok(self.species.clone())
+        }
+    }
+
+    pub fn pair_betas(&self) -> Result<Option<AttractionMatrix>, ConfigError> {
+        let Some(values) = &self.pair_betas else {
+            return Ok(None);
+        };
+        if let Some(beta) = values.iter().find(|b| !(**b > 0. && **b < 1.)) {
+            return Err(ConfigError::Invalid(format!(
+                "pair_betas must all be between 0 and 1 (exclusive), got {}",
+                beta
+            )));
+        }
+        AttractionMatrix::from_values(self.species_count()?, values.clone())
+            .map(Some)
+            .map_err(|e| ConfigError::Invalid(format!("pair_betas: {}", e)))
+    }
+
+    pub fn pair_radii(&self) -> Result<Option<AttractionMatrix>, ConfigError> {
+        let Some(values) = &self.pair_radii else {
+            return Ok(None);
+        };
+        for radius in values {
+            positive("pair_radii", *radius)?;
+        }
+        AttractionMatrix::from_values(self.species_count()?, values.clone())
+            .map(Some)
+            .map_err(|e| ConfigError::Invalid(format!("pair_radii: {}", e)))
+    }
+
+    /// The density map for `layout = "image"`, loaded from `layout_image`.
+    pub fn density(&self) -> Result<Option<DensityMap>, ConfigError> {
+        let Some(path) = &self.layout_image else {
+            return Ok(None);
+        };
+        let density = DensityMap::load(path).map_err(|e| {
+            ConfigError::Invalid(format!("could not load {}: {}", path.display(), e))
+        })?;
+        if density.is_empty() {
+            return Err(ConfigError::Invalid(format!(
+                "{} is completely black, so there is nowhere to place particles",
+                path.display()
+            )));
+        }
+        Ok(Some(density))
+    }
+
+    pub fn species_count(&self) -> Result<usize, ConfigError> {
+        self.palette().map(|colors| colors.len())
+    }
+
+    /// One colour per species, from `colors`, `palette` or the default
+    /// classic five. Asking for more species than a fixed palette has is an
+    /// error, except that without any palette set an HSV palette is generated.
+    pub fn palette(&self) -> Result<Vec<[f32; 4]>, ConfigError> {
+        let colors = match (&self.colors, self.palette.as_deref()) {
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::Invalid(
+                    "set either colors or palette, not both".to_string(),
+                ))
+            }
+            (Some(names), None) => {
+                let colors = names
+                    .iter()
+                    .map(|c| {
+                        palette::parse_color(c).ok_or_else(|| {
+                            ConfigError::Invalid(format!(
+                                "unknown colour {:?}, expected a name such as \"red\" or a hex code such as \"#ff8800\"",
+                                c
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<[f32; 4]>, ConfigError>>()?;
+                if let Some(count) = self.num_species.filter(|n| *n != colors.len()) {
+                    return Err(ConfigError::Invalid(format!(
+                        "num_species is {} but colors lists {}",
+                        count,
+                        colors.len()
+                    )));
+                }
+                colors
+            }
+            (None, Some("hsv")) => palette::hsv(self.num_species.unwrap_or(DEFAULT_SPECIES)),
+            (None, Some(name)) => {
+                let colors = palette::preset(name).ok_or_else(|| {
+                    ConfigError::Invalid(format!(
+                        "unknown palette {:?}, expected one of {}",
+                        name,
+                        palette::PRESETS.join(", ")
+                    ))
+                })?;
+                match self.num_species {
+                    Some(count) if count > colors.len() => {
+                        return Err(ConfigError::Invalid(format!(
+                            "palette {:?} only has {} colours but num_species is {}; use palette = \"hsv\" for more",
+                            name,
+                            colors.len(),
+                            count
+                        )))
+                    }
+                    Some(count) => colors[..count].to_vec(),
+                    None => colors,
+                }
+            }
+            (None, None) => {
+                let classic = palette::preset("classic").expect("classic is a preset");
+                match self.num_species {
+                    Some(count) if count > classic.len() => palette::hsv(count),
+                    Some(count) => classic[..count].to_vec(),
+                    None => classic,
+                }
+            }
+        };
+        if colors.len() < MIN_SPECIES || colors.len() > MAX_SPECIES {
+            return Err(ConfigError::Invalid(format!(
+                "there must be between {} and {} species, got {}",
+                MIN_SPECIES,
+                MAX_SPECIES,
+                colors.len()
+            )));
+        }
+        Ok(colors)
+    }
+}
+
+// Every float in a config is an f32, which TOML would otherwise write out at
+// full f64 precision (0.1 becomes 0.10000000149011612)
+fn shorten_floats(value: &mut toml::Value) {
+    match value {
+        toml::Value::Float(f) => {
+            *f = (*f as f32).to_string().parse().unwrap_or(*f);
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(shorten_floats),
+        toml::Value::Table(table) => table.iter_mut().for_each(|(_, v)| shorten_floats(v)),
+        _ => {}
+    }
+}
+
+fn positive(name: &str, value: f32) -> Result<(), ConfigError> {
+    if value > 0. && value.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!(
+            "{} must be a positive number, got {}",
+            name, value
+        )))
+    }
+}
@@ -0,0 +1,85 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Direction of a `ForceField` relative to its centre.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldMode {
+    Attract,
+    Repel,
+    /// Anticlockwise around the centre
+    Vortex,
+}
+
+impl FieldMode {
+    pub fn next(self) -> Self {
+        match self {
+            FieldMode::Attract => FieldMode::Repel,
+            FieldMode::Repel => FieldMode::Vortex,
+            FieldMode::Vortex => FieldMode::Attract,
+        }
+    }
+}
+
+/// An external force around a point such as the mouse cursor, applied to
+/// every particle as part of the physics step.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ForceField {
+    pub mode: FieldMode,
+    /// Acceleration at the centre
+    pub strength: f32,
+    /// Distance at which the force has fallen linearly to zero. Without one
+    /// the force reaches the whole world at full strength
+    pub radius: Option<f32>,
+}
+
+impl Default for ForceField {
+    fn default() -> Self {
+        ForceField {
+            mode: FieldMode::Attract,
+            strength: 10.,
+            radius: None,
+        }
+    }
+}
+
+impl ForceField {
+    /// Acceleration of a particle whose offset to the field centre is
+    /// `to_centre`. Zero at the centre itself, so a particle sitting exactly
+    /// on the cursor is left alone instead of turning into NaN.
+    pub fn acceleration(&self, to_centre: Vec2) -> Vec2 {
+        let distance = to_centre.length();
+        if !(distance > 0. && distance.is_finite()) {
+            return Vec2::ZERO;
+        }
+        let falloff = match self.radius {
+            Some(radius) if distance >= radius => return Vec2::ZERO,
+            Some(radius) => 1. - distance / radius,
+            None => 1.,
+        };
+        let direction = to_centre / distance;
+        let direction = match self.mode {
+            FieldMode::Attract => direction,
+            FieldMode::Repel => -direction,
+            FieldMode::Vortex => direction.perp(),
+        };
+        direction * (self.strength * falloff)
+    }
+
+    /// Describes the first out-of-range value, if any.
+    pub fn check(&self) -> Result<(), String> {
+        if !(self.strength >= 0. && self.strength.is_finite()) {
+            return Err(format!(
+                "strength must be zero or positive, got {}",
+                self.strength
+            ));
+        }
+        if let Some(radius) = self.radius {
+            if !(radius > 0. && radius.is_finite()) {
+                return Err(format!("radius must be a positive number, got {}", radius));
+            }
+        }
+        Ok(())
+    }
+}
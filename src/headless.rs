@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use macroquad::prelude::*;
+use particle_life::recording::Recorder;
+use particle_life::render::{render, View};
+use particle_life::trajectory::TrajectoryWriter;
+use particle_life::{snapshot, Particle, Simulation};
+
+#[derive(Args)]
+pub struct HeadlessArgs {
+    /// Number of simulation steps to run
+    #[arg(long, default_value_t = 1000)]
+    steps: usize,
+    /// CSV file to write the final population to
+    #[arg(long, default_value = "population.csv")]
+    output: PathBuf,
+    /// Also write a snapshot of the final state that can be resumed with --load
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+    /// Worker threads for the physics (defaults to one per core); results
+    /// are identical for any thread count
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Render frames offscreen and record them: a directory of PNGs, a .gif,
+    /// or a raw RGBA stream (.rgba, or - for stdout)
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Resolution of recorded frames, as WIDTHxHEIGHT
+    #[arg(long, default_value = "800x800", value_parser = crate::parse_size)]
+    record_size: (u32, u32),
+    /// Record a frame every this many steps
+    #[arg(long, default_value_t = 1)]
+    record_every: usize,
+    /// Stream particle positions and velocities to this file: .csv for text,
+    /// anything else for the compact binary format
+    #[arg(long)]
+    trajectory: Option<PathBuf>,
+    /// Write a trajectory frame every this many steps
+    #[arg(long, default_value_t = 1)]
+    trajectory_every: usize,
+    /// Render the final state to this PNG
+    #[arg(long)]
+    screenshot: Option<PathBuf>,
+    /// Resolution of the screenshot, as WIDTHxHEIGHT
+    #[arg(long, default_value = "3200x3200", value_parser = crate::parse_size)]
+    screenshot_size: (u32, u32),
+}
+
+// Run the physics for `steps` steps without opening a window, then write the
+// final population to `output` as CSV and optionally a resumable snapshot
+pub fn run(
+    mut sim: Simulation,
+    colors: &[[f32; 4]],
+    args: &HeadlessArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads.unwrap_or(0))
+        .build()?;
+    let every = args.record_every.max(1);
+    let mut recorder = match &args.record {
+        Some(path) => Some(Recorder::start(path, (60 / every).max(1) as u32)?),
+        None => None,
+    };
+    let trajectory_every = args.trajectory_every.max(1);
+    let mut trajectory = match &args.trajectory {
+        Some(path) => Some(TrajectoryWriter::create(path)?),
+        None => None,
+    };
+    let (width, height) = args.record_size;
+    pool.install(|| -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Trajectory frames are labelled with the number of steps taken, so
+        // the initial state is step 0
+        if let Some(trajectory) = &mut trajectory {
+            trajectory.write(0, &sim.population)?;
+        }
+        for step in 0..args.steps {
+            if let Some(recorder) = &mut recorder {
+                if step % every == 0 {
+                    let frame = render(&sim.population, colors, View::default(), width, height);
+                    recorder.push(frame)?;
+                }
+            }
+            sim.step();
+            if let Some(trajectory) = &mut trajectory {
+                if (step + 1) % trajectory_every == 0 {
+                    trajectory.write((step + 1) as u64, &sim.population)?;
+                }
+            }
+        }
+        Ok(())
+    })
+    .map_err(|e| e as Box<dyn std::error::Error>)?;
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+    if let Some(trajectory) = trajectory {
+        trajectory.finish()?;
+    }
+    write_population(&sim.population, &args.output)
+        .map_err(|e| format!("could not write {}: {}", args.output.display(), e))?;
+    if let Some(path) = &args.snapshot {
+        snapshot::save(&sim, path)?;
+    }
+    if let Some(path) = &args.screenshot {
+        let (width, height) = args.screenshot_size;
+        render(&sim.population, colors, View::default(), width, height)
+            .save(path)
+            .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn write_population(population: &[Particle], output: &Path) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(output)?);
+    writeln!(file, "id,color,x,y,vx,vy")?;
+    for p in population {
+        writeln!(
+            file,
+            "{},{},{},{},{},{}",
+            p.id, p.color, p.position.x, p.position.y, p.velocity.x, p.velocity.y
+        )?;
+    }
+    file.flush()
+}
@@ -0,0 +1,86 @@
+use macroquad::prelude::*;
+use particle_life::Simulation;
+
+const FONT_SIZE: f32 = 20.;
+const LINE_HEIGHT: f32 = 20.;
+const LEFT: f32 = 10.;
+const TOP: f32 = 60.;
+// Weight of the newest sample in the smoothed step time
+const SMOOTHING: f32 = 0.1;
+
+// Run statistics under the FPS counter
+pub struct Hud {
+    pub visible: bool,
+    step_ms: Option<f32>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud {
+            visible: false,
+            step_ms: None,
+        }
+    }
+
+    // Feeds in the wall-clock time spent on `steps` steps this frame
+    pub fn record_steps(&mut self, steps: usize, seconds: f32) {
+        if steps == 0 {
+            return;
+        }
+        let sample = seconds * 1000. / steps as f32;
+        self.step_ms = Some(match self.step_ms {
+            Some(ms) => ms + (sample - ms) * SMOOTHING,
+            None => sample,
+        });
+    }
+
+    pub fn draw(&self, sim: &Simulation, colors: &[Color]) {
+        if !self.visible {
+            return;
+        }
+        let params = &sim.params;
+        let step_ms = self
+            .step_ms
+            .map_or("-".to_string(), |ms| format!("{:.2} ms", ms));
+        let lines = [
+            format!("Particles {}", sim.population.len()),
+            String::new(), // species counts, drawn in colour below
+            format!("Step {}  t = {:.2}", sim.steps, sim.time),
+            format!("Step time {}", step_ms),
+            format!("Mean kinetic energy {:.3e}", sim.kinetic_energy()),
+            format!("Seed {}", params.seed),
+            format!(
+                "radius {}  dt {}  friction {}  beta {}  pull {}",
+                params.max_radius,
+                params.time_step,
+                params.friction_half_life,
+                params.beta,
+                params.centre_pull
+            ),
+            format!(
+                "{:?} / {:?} / {:?} / {:?} / {:?}",
+                params.search, params.boundary, params.kernel, params.integrator, params.layout
+            ),
+        ];
+        let width = lines
+            .iter()
+            .map(|l| measure_text(l, None, FONT_SIZE as u16, 1.).width)
+            .fold(0., f32::max);
+        draw_rectangle(
+            LEFT - 5.,
+            TOP - LINE_HEIGHT,
+            width + 10.,
+            LINE_HEIGHT * lines.len() as f32 + 5.,
+            Color::new(0., 0., 0., 0.6),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, LEFT, TOP + LINE_HEIGHT * i as f32, FONT_SIZE, WHITE);
+        }
+        let mut x = LEFT;
+        for (count, color) in sim.species_counts().iter().zip(colors) {
+            let text = count.to_string();
+            draw_text(&text, x, TOP + LINE_HEIGHT, FONT_SIZE, *color);
+            x += measure_text(&text, None, FONT_SIZE as u16, 1.).width + 12.;
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Particle;
+
+/// Numerical schemes for advancing positions and velocities by one step.
+/// Friction is not part of the scheme: velocities decay exactly by the
+/// species' friction factor first, then the integrator applies the forces.
+/// That split is only first-order accurate, so the orders below describe how
+/// the forces are integrated, not the whole step while friction is on.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Integrator {
+    /// Position from the old velocity, velocity from the old forces
+    ExplicitEuler,
+    /// Velocity first, then position from the new velocity
+    SemiImplicitEuler,
+    /// Second-order in the forces, with two force evaluations per step
+    VelocityVerlet,
+    /// Classic Runge-Kutta, fourth-order in the forces, with four force
+    /// evaluations per step
+    Rk4,
+}
+
+impl Integrator {
+    pub fn next(self) -> Self {
+        match self {
+            Integrator::ExplicitEuler => Integrator::SemiImplicitEuler,
+            Integrator::SemiImplicitEuler => Integrator::VelocityVerlet,
+            Integrator::VelocityVerlet => Integrator::Rk4,
+            Integrator::Rk4 => Integrator::ExplicitEuler,
+        }
+    }
+
+    /// Advance `state` by `dt`. `friction` gives the factor a particle's
+    /// velocity decays by over `dt`, and `accelerations` the acceleration of
+    /// every particle in a given state.
+    pub fn advance(
+        self,
+        state: &[Particle],
+        dt: f32,
+        friction: impl Fn(&Particle) -> f32,
+        accelerations: impl Fn(&[Particle]) -> Vec<Vec2>,
+    ) -> Vec<Particle> {
+        let mut state: Vec<Particle> = state
+            .iter()
+            .map(|p| Particle {
+                velocity: p.velocity * friction(p),
+                ..*p
+            })
+            .collect();
+        let a0 = accelerations(&state);
+        match self {
+            Integrator::ExplicitEuler => {
+                for (p, a) in state.iter_mut().zip(a0) {
+                    p.position += p.velocity * dt;
+                    p.velocity += a * dt;
+                }
+            }
+            Integrator::SemiImplicitEuler => {
+                for (p, a) in state.iter_mut().zip(a0) {
+                    p.velocity += a * dt;
+                    p.position += p.velocity * dt;
+                }
+            }
+            Integrator::VelocityVerlet => {
+                for (p, a) in state.iter_mut().zip(&a0) {
+                    p.position += p.velocity * dt + *a * (0.5 * dt * dt);
+                }
+                let a1 = accelerations(&state);
+                for ((p, a0), a1) in state.iter_mut().zip(a0).zip(a1) {
+                    p.velocity += (a0 + a1) * (0.5 * dt);
+                }
+            }
+            Integrator::Rk4 => {
+                // Each stage is the state a fraction of the way through the
+                // step along the previous stage's slope
+                let stage = |v: &[Vec2], a: &[Vec2], h: f32| -> Vec<Particle> {
+                    state
+                        .iter()
+                        .zip(v.iter().zip(a))
+                        .map(|(p, (v, a))| Particle {
+                            position: p.position + *v * h,
+                            velocity: p.velocity + *a * h,
+                            ..*p
+                        })
+                        .collect()
+                };
+                let v0: Vec<Vec2> = state.iter().map(|p| p.velocity).collect();
+                let s1 = stage(&v0, &a0, 0.5 * dt);
+                let a1 = accelerations(&s1);
+                let v1: Vec<Vec2> = s1.iter().map(|p| p.velocity).collect();
+                let s2 = stage(&v1, &a1, 0.5 * dt);
+                let a2 = accelerations(&s2);
+                let v2: Vec<Vec2> = s2.iter().map(|p| p.velocity).collect();
+                let s3 = stage(&v2, &a2, dt);
+                let a3 = accelerations(&s3);
+                let v3: Vec<Vec2> = s3.iter().map(|p| p.velocity).collect();
+                for (i, p) in state.iter_mut().enumerate() {
+                    p.position += (v0[i] + 2. * v1[i] + 2. * v2[i] + v3[i]) * (dt / 6.);
+                    p.velocity += (a0[i] + 2. * a1[i] + 2. * a2[i] + a3[i]) * (dt / 6.);
+                }
+            }
+        }
+        state
+    }
+}
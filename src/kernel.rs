@@ -0,0 +1,109 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+/// Interaction law between two particles. `r` is the distance divided by the
+/// interaction radius, `a` the attraction coefficient for the pair and `beta`
+/// the distance below which particles repel. Positive values pull the pair
+/// together.
+pub trait ForceKernel: Send + Sync {
+    fn force(&self, r: f32, a: f32, beta: f32) -> f32;
+}
+
+/// The classic particle-life kernel: linear repulsion below `beta`, then a
+/// triangular attraction peaking halfway between `beta` and 1.
+pub struct Linear;
+
+impl ForceKernel for Linear {
+    fn force(&self, r: f32, a: f32, beta: f32) -> f32 {
+        if r < beta {
+            r / beta - 1.
+        } else if (beta < r) && (r < 1.) {
+            a * (1. - (2. * r - 1. - beta).abs() / (1. - beta))
+        } else {
+            0.
+        }
+    }
+}
+
+/// Same shape as `Linear` with the corners rounded off by cosines.
+pub struct Cosine;
+
+impl ForceKernel for Cosine {
+    fn force(&self, r: f32, a: f32, beta: f32) -> f32 {
+        if r < beta {
+            -0.5 * (1. + (PI * r / beta).cos())
+        } else if r < 1. {
+            a * (PI * (r - beta) / (1. - beta)).sin()
+        } else {
+            0.
+        }
+    }
+}
+
+/// Steep 12-6 repulsive core inside `beta` and a Lennard-Jones-shaped well
+/// outside it, tapered to reach zero at the interaction radius.
+pub struct LennardJones;
+
+impl ForceKernel for LennardJones {
+    fn force(&self, r: f32, a: f32, beta: f32) -> f32 {
+        if r <= 0. || r >= 1. {
+            return 0.;
+        }
+        let x6 = (beta / r).powi(6);
+        let lj = x6 - x6 * x6;
+        if r < beta {
+            lj.max(-1.)
+        } else {
+            // 4 * lj peaks at exactly 1
+            a * 4. * lj * (1. - r) / (1. - beta)
+        }
+    }
+}
+
+/// Gaussian repulsion centred on zero plus a Gaussian attraction bump
+/// centred halfway between `beta` and 1.
+pub struct Gaussian;
+
+impl ForceKernel for Gaussian {
+    fn force(&self, r: f32, a: f32, beta: f32) -> f32 {
+        if r >= 1. {
+            return 0.;
+        }
+        let repel = (-0.5 * (2. * r / beta).powi(2)).exp();
+        let centre = (1. + beta) / 2.;
+        let width = (1. - beta) / 6.;
+        let attract = (-0.5 * ((r - centre) / width).powi(2)).exp();
+        a * attract - repel
+    }
+}
+
+/// Built-in kernels, selectable by name from config.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelKind {
+    Linear,
+    Cosine,
+    LennardJones,
+    Gaussian,
+}
+
+impl KernelKind {
+    pub fn kernel(self) -> &'static dyn ForceKernel {
+        match self {
+            KernelKind::Linear => &Linear,
+            KernelKind::Cosine => &Cosine,
+            KernelKind::LennardJones => &LennardJones,
+            KernelKind::Gaussian => &Gaussian,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            KernelKind::Linear => KernelKind::Cosine,
+            KernelKind::Cosine => KernelKind::LennardJones,
+            KernelKind::LennardJones => KernelKind::Gaussian,
+            KernelKind::Gaussian => KernelKind::Linear,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+pub mod boundary;
+pub mod config;
+pub mod field;
+pub mod integrator;
+pub mod kernel;
+pub mod matrix;
+pub mod neighbours;
+pub mod palette;
+pub mod recording;
+pub mod render;
+pub mod simulation;
+pub mod snapshot;
+pub mod spawn;
+pub mod species;
+pub mod stepper;
+pub mod trajectory;
+
+pub use boundary::Boundary;
+pub use config::{Config, ConfigError};
+pub use field::{FieldMode, ForceField};
+pub use integrator::Integrator;
+pub use kernel::{ForceKernel, KernelKind};
+pub use matrix::AttractionMatrix;
+pub use neighbours::NeighbourSearch;
+pub use simulation::{Params, Particle, Simulation};
+pub use snapshot::{Snapshot, SnapshotError};
+pub use spawn::Layout;
+pub use species::Species;
+pub use stepper::FixedStepper;
@@ -1,15 +1,28 @@
-use ::rand::distributions::{Distribution, Uniform};
-use ::rand::prelude::*;
+use clap::{Args, Parser, Subcommand};
+use image::RgbaImage;
 use macroquad::prelude::*;
-use rand_chacha::ChaCha8Rng;
-use rayon::prelude::*;
+use particle_life::palette;
+use particle_life::recording::{RecordError, Recorder};
+use particle_life::render::render;
+use particle_life::{
+    snapshot, Boundary, Config, ConfigError, FixedStepper, Layout, Particle, Simulation,
+};
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(PartialEq, Clone, Copy)]
-struct Particle {
-    color: usize,
-    position: Vec2,
-    velocity: Vec2,
-}
+mod camera;
+mod headless;
+mod hud;
+mod overlay;
+mod panel;
+mod tools;
+
+use camera::Camera;
+use headless::HeadlessArgs;
+use hud::Hud;
+use overlay::MatrixOverlay;
+use panel::ParamPanel;
+use tools::Brush;
 
 fn conf() -> Conf {
     Conf {
@@ -21,131 +34,426 @@ fn conf() -> Conf {
     }
 }
 
-fn flat_matrix(side_length: usize, rand_obj: &mut ChaCha8Rng) -> Vec<f32> {
-    let dist = Uniform::from(-1f32..1f32);
-    (0..(side_length * side_length))
-        .map(|_| dist.sample(rand_obj))
-        .collect()
-}
-
-fn reset_attraction(
-    attractions: Vec<f32>,
-    colours: &Vec<Color>,
-    rand_obj: &mut ChaCha8Rng,
-) -> Vec<f32> {
-    if is_key_pressed(KeyCode::Space) {
-        flat_matrix(colours.len(), rand_obj)
+fn mouse_target(camera: &Camera) -> Option<Vec2> {
+    if is_mouse_button_down(MouseButton::Left) {
+        Some(camera.screen_to_world(Vec2::from(mouse_position())))
     } else {
-        attractions
+        None
     }
 }
 
-fn generate_population(num_particles: usize, color_array: &Vec<Color>) -> Vec<Particle> {
-    (0..num_particles)
-        .map(|_| Particle {
-            color: rand::gen_range(0, color_array.len()),
-            position: vec2(rand::gen_range(0., 1.), rand::gen_range(0., 1.)),
-            velocity: Vec2::ZERO,
-        })
-        .collect()
+fn draw_fps() {
+    draw_text(format!("FPS {}", get_fps()).as_str(), 10., 30., 30., WHITE);
 }
 
-fn force(r: f32, a: f32, beta: f32) -> f32 {
-    if r < beta {
-        r / beta - 1.
-    } else if (beta < r) && (r < 1.) {
-        a * (1. - (2. * r - 1. - beta).abs() / (1. - beta))
+// Playback state, to the right of the FPS counter
+fn draw_playback(stepper: &FixedStepper) {
+    let status = if stepper.paused {
+        "PAUSED".to_string()
+    } else if stepper.speed >= 1. {
+        format!("x{}", stepper.speed)
     } else {
-        0.
+        format!("x1/{}", 1. / stepper.speed)
+    };
+    draw_text(&status, 150., 30., 30., YELLOW);
+}
+
+fn draw_particles(pop: &[Particle], color_array: &[Color], camera: &Camera) {
+    let size = camera.particle_size();
+    for p in pop {
+        let screen = camera.world_to_screen(p.position);
+        draw_circle(screen.x, screen.y, size, color_array[p.color]);
     }
 }
 
-fn update_population(population: &Vec<Particle>, attractions: &Vec<f32>) -> Vec<Particle> {
-    let friction_factor: f32 = 0.5_f32.powf(TIME_STEP / FRICTION_HALF_LIFE);
-    population
-        .par_iter()
-        .map(|p1| {
-            let mut total_force = Vec2::ZERO;
-            for p2 in population {
-                if p1 == p2 {
-                    continue;
-                };
-                let distance = p1.position.distance(p2.position);
-                if (distance > 0.) & (distance < MAX_RADIUS) {
-                    let f = force(
-                        distance / MAX_RADIUS,
-                        attractions[(p1.color * 3) + p2.color],
-                        0.3,
-                    );
-                    total_force += ((p2.position - p1.position) / distance) * f;
-                }
-            }
-            total_force *= MAX_RADIUS;
+// Ring and label the tracked particle so it can be picked out of a crowd
+fn draw_tracked(pop: &[Particle], camera: &Camera) {
+    let Some(p) = camera
+        .tracking
+        .and_then(|id| pop.iter().find(|p| p.id == id))
+    else {
+        return;
+    };
+    let screen = camera.world_to_screen(p.position);
+    let radius = camera.particle_size() + 6.;
+    draw_circle_lines(screen.x, screen.y, radius, 1.5, YELLOW);
+    draw_text(
+        &format!("#{}", p.id),
+        screen.x + radius + 2.,
+        screen.y - radius,
+        20.,
+        YELLOW,
+    );
+}
 
-            // Create new particle with velocity driven by this force
-            let mut new_p = *p1;
-            new_p.velocity *= friction_factor;
-            new_p.velocity += total_force * TIME_STEP;
+// Outline the unit square when its edges actually do something
+fn draw_world_edges(boundary: Boundary, camera: &Camera) {
+    if matches!(boundary, Boundary::Wrap | Boundary::Reflect) {
+        let min = camera.world_to_screen(Vec2::ZERO);
+        let max = camera.world_to_screen(Vec2::ONE);
+        draw_rectangle_lines(min.x, min.y, max.x - min.x, max.y - min.y, 2., DARKGRAY);
+    }
+}
 
-            // Push toward centre
-            new_p.velocity -= (new_p.position - vec2(0.5, 0.5)) / 128.;
+// Simulation steps per second of wall-clock time in the window
+const STEPS_PER_SECOND: f32 = 60.;
 
-            // Update position based on this velocity
-            new_p.position += new_p.velocity * TIME_STEP;
-            new_p
-        })
-        .collect()
+#[derive(Parser)]
+#[command(about = "Particle Life simulation")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// TOML file with simulation settings; flags below override its values
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Resume from a snapshot file instead of generating a new world
+    #[arg(long, global = true)]
+    load: Option<PathBuf>,
+    /// Where `V` records frames to: a directory of PNGs, a .gif, or a raw
+    /// RGBA stream (.rgba, or - for stdout). Defaults to a new PNG directory
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Resolution of `F12` screenshots, as WIDTHxHEIGHT. Defaults to four
+    /// times the window size
+    #[arg(long, value_parser = parse_size)]
+    screenshot_size: Option<(u32, u32)>,
+    #[command(flatten)]
+    overrides: Overrides,
 }
 
-fn attract_to_mouse(mut pop: Vec<Particle>) -> Vec<Particle> {
-    if is_mouse_button_down(MouseButton::Left) {
-        let (mouse_x, mouse_y) = mouse_position();
-        let mouse_pos = vec2(mouse_x / screen_width(), mouse_y / screen_height());
-        pop = pop
-            .iter_mut()
-            .map(|p| {
-                p.velocity -= (p.position - mouse_pos).normalize() * 0.1;
-                *p
-            })
-            .collect();
+#[derive(Args)]
+struct Overrides {
+    /// Seed for the attraction matrix and initial population
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// Interaction radius, as a fraction of the world size
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    max_radius: Option<f32>,
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    time_step: Option<f32>,
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    friction_half_life: Option<f32>,
+    /// Distance (relative to max radius) below which particles repel
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    beta: Option<f32>,
+    #[arg(long, global = true)]
+    num_particles: Option<usize>,
+    /// Colour palette preset: classic, bright, pastel, earth or hsv
+    #[arg(long, global = true)]
+    palette: Option<String>,
+    /// Number of species, from 2 to 32
+    #[arg(long, global = true)]
+    num_species: Option<usize>,
+}
+
+impl Overrides {
+    fn apply(&self, config: &mut Config) {
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+        if let Some(max_radius) = self.max_radius {
+            config.max_radius = max_radius;
+        }
+        if let Some(time_step) = self.time_step {
+            config.time_step = time_step;
+        }
+        if let Some(friction_half_life) = self.friction_half_life {
+            config.friction_half_life = friction_half_life;
+        }
+        if let Some(beta) = self.beta {
+            config.beta = beta;
+        }
+        if let Some(num_particles) = self.num_particles {
+            config.num_particles = num_particles;
+        }
+        if let Some(palette) = &self.palette {
+            // A preset on the command line replaces any colour list
+            config.colors = None;
+            config.palette = Some(palette.clone());
+        }
+        if let Some(num_species) = self.num_species {
+            config.num_species = Some(num_species);
+        }
     }
-    pop
 }
 
-fn draw_fps() -> () {
-    draw_text(format!("FPS {}", get_fps()).as_str(), 10., 30., 30., WHITE);
+fn load_config(cli: &Cli) -> Result<Config, ConfigError> {
+    let mut config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    cli.overrides.apply(&mut config);
+    config.validate()?;
+    Ok(config)
 }
 
-fn draw_particles(pop: &Vec<Particle>, color_array: &Vec<Color>) -> () {
-    for p in pop {
-        draw_circle(
-            p.position.x * screen_width() as f32,
-            p.position.y * screen_height() as f32,
-            2.,
-            color_array[p.color],
+#[derive(Subcommand)]
+enum Command {
+    /// Run the simulation without a window and write the final state to disk
+    Headless(HeadlessArgs),
+}
+
+// Parses WIDTHxHEIGHT, e.g. 1920x1080
+fn parse_size(text: &str) -> Result<(u32, u32), String> {
+    let (w, h) = text
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {:?}", text))?;
+    let parse = |v: &str| match v.trim().parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{:?} is not a positive whole number of pixels", v)),
+    };
+    Ok((parse(w)?, parse(h)?))
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let config = match load_config(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let colors = config.palette().expect("palette is checked by validate");
+    let sim = match &cli.load {
+        Some(path) => match snapshot::load(path) {
+            Ok(sim) => sim,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => config.simulation().expect("config is checked by validate"),
+    };
+    let colors = if sim.attractions.size() > colors.len() {
+        eprintln!(
+            "the simulation has {} species but only {} colours are configured; using a generated palette",
+            sim.attractions.size(),
+            colors.len()
         );
+        palette::hsv(sim.attractions.size())
+    } else {
+        colors
+    };
+    match cli.command {
+        Some(Command::Headless(args)) => {
+            if let Err(e) = headless::run(sim, &colors, &args) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        None => {
+            macroquad::Window::from_config(
+                conf(),
+                run_window(
+                    sim,
+                    colors,
+                    config,
+                    cli.config,
+                    cli.record,
+                    cli.screenshot_size,
+                ),
+            );
+        }
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn save_snapshot(sim: &Simulation) {
+    let path = PathBuf::from(format!("snapshot-{}.json", timestamp()));
+    match snapshot::save(sim, &path) {
+        Ok(()) => eprintln!("Saved snapshot to {}", path.display()),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+// Renders what the camera sees offscreen, so the still can be much larger than
+// the window
+fn save_screenshot(
+    sim: &Simulation,
+    colors: &[[f32; 4]],
+    camera: &Camera,
+    size: Option<(u32, u32)>,
+) {
+    let (width, height) = size.unwrap_or((4 * screen_width() as u32, 4 * screen_height() as u32));
+    let path = PathBuf::from(format!("screenshot-{}.png", timestamp()));
+    match render(&sim.population, colors, camera.view(), width, height).save(&path) {
+        Ok(()) => eprintln!(
+            "Saved {}x{} screenshot to {}",
+            width,
+            height,
+            path.display()
+        ),
+        Err(e) => eprintln!("could not write {}: {}", path.display(), e),
+    }
+}
+
+// Starts a recording if none is running, otherwise finishes the current one
+fn toggle_recording(recorder: Option<Recorder>, path: &Option<PathBuf>) -> Option<Recorder> {
+    match recorder {
+        Some(recorder) => {
+            let (path, frames) = (recorder.path().to_path_buf(), recorder.frames());
+            match recorder.finish() {
+                Ok(()) => eprintln!("Recorded {} frames to {}", frames, path.display()),
+                Err(e) => eprintln!("{}", e),
+            }
+            None
+        }
+        None => {
+            let path = path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(format!("recording-{}", timestamp())));
+            match Recorder::start(&path, STEPS_PER_SECOND as u32) {
+                Ok(recorder) => {
+                    eprintln!("Recording to {}", path.display());
+                    Some(recorder)
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            }
+        }
     }
 }
 
-const SEED: u64 = 50;
-const MAX_RADIUS: f32 = 0.1;
-const TIME_STEP: f32 = 0.01;
-const FRICTION_HALF_LIFE: f32 = 0.04;
-const NUM_PARTICLES: usize = 4000;
+// Grab the frame just drawn. GL reads the framebuffer bottom-up, so flip it
+fn capture_frame(recorder: &mut Recorder) -> Result<(), RecordError> {
+    let screen = get_screen_data();
+    let mut frame = RgbaImage::from_raw(screen.width as u32, screen.height as u32, screen.bytes)
+        .expect("screen data matches its dimensions");
+    image::imageops::flip_vertical_in_place(&mut frame);
+    recorder.push(frame)
+}
 
-#[macroquad::main(conf)]
-async fn main() {
-    let mut rng = ChaCha8Rng::seed_from_u64(SEED);
-    let colors: Vec<Color> = vec![RED, BLUE, GREEN, WHITE, PINK];
-    let mut attraction_matrix = flat_matrix(colors.len(), &mut rng);
-    let mut population: Vec<Particle> = generate_population(NUM_PARTICLES, &colors);
+async fn run_window(
+    mut sim: Simulation,
+    rgba: Vec<[f32; 4]>,
+    mut config: Config,
+    config_path: Option<PathBuf>,
+    record_path: Option<PathBuf>,
+    screenshot_size: Option<(u32, u32)>,
+) {
+    let colors: Vec<Color> = rgba.iter().copied().map(Color::from).collect();
+    let mut overlay = MatrixOverlay::new();
+    let mut hud = Hud::new();
+    let mut panel = ParamPanel::new();
+    let mut brush = Brush::new();
+    let mut recorder: Option<Recorder> = None;
+    let mut camera = Camera::new();
+    let mut stepper = FixedStepper::new(STEPS_PER_SECOND);
     loop {
         clear_background(BLACK);
-        attraction_matrix = reset_attraction(attraction_matrix, &colors, &mut rng);
-        population = update_population(&population, &attraction_matrix);
-        population = attract_to_mouse(population);
-        draw_particles(&population, &colors);
+        if is_key_pressed(KeyCode::Tab) {
+            panel.visible = !panel.visible;
+        }
+        let over_ui = panel.update(&mut sim, &mut config, config_path.as_deref())
+            || overlay.handle_input(&mut sim.attractions);
+        // The panel's edit boxes take digits, '.' and '-', which are also
+        // brush keys
+        if !panel.wants_keyboard() {
+            if is_key_pressed(KeyCode::Space) {
+                sim.randomise_attractions();
+            }
+            if is_key_pressed(KeyCode::S) {
+                save_snapshot(&sim);
+            }
+            if is_key_pressed(KeyCode::V) {
+                recorder = toggle_recording(recorder.take(), &record_path);
+            }
+            if is_key_pressed(KeyCode::F12) {
+                save_screenshot(&sim, &rgba, &camera, screenshot_size);
+            }
+            if is_key_pressed(KeyCode::G) {
+                sim.params.field.mode = sim.params.field.mode.next();
+            }
+            if is_key_pressed(KeyCode::R) {
+                sim.reset_population();
+                camera.tracking = None;
+            }
+            if is_key_pressed(KeyCode::L) {
+                sim.params.layout = sim.params.layout.next();
+                // Only offer the image layout when there is an image to use
+                if sim.params.layout == Layout::Image && sim.density.is_none() {
+                    sim.params.layout = sim.params.layout.next();
+                }
+                sim.reset_population();
+                camera.tracking = None;
+            }
+            if is_key_pressed(KeyCode::B) {
+                sim.params.search = sim.params.search.next();
+            }
+            if is_key_pressed(KeyCode::W) {
+                sim.set_boundary(sim.params.boundary.next());
+            }
+            if is_key_pressed(KeyCode::K) {
+                sim.params.kernel = sim.params.kernel.next();
+            }
+            if is_key_pressed(KeyCode::I) {
+                sim.params.integrator = sim.params.integrator.next();
+            }
+            if is_key_pressed(KeyCode::H) {
+                hud.visible = !hud.visible;
+            }
+            if is_key_pressed(KeyCode::M) {
+                overlay.visible = !overlay.visible;
+            }
+            if is_key_pressed(KeyCode::F) {
+                camera.follow = !camera.follow;
+                camera.tracking = None;
+            }
+            if is_key_pressed(KeyCode::T) {
+                camera.tracking = camera.pick(&sim.population, Vec2::from(mouse_position()));
+            }
+            if is_key_pressed(KeyCode::P) {
+                stepper.toggle_pause();
+            }
+            if is_key_pressed(KeyCode::N) {
+                stepper.single_step();
+            }
+            if is_key_pressed(KeyCode::RightBracket) {
+                stepper.faster();
+            }
+            if is_key_pressed(KeyCode::LeftBracket) {
+                stepper.slower();
+            }
+            if is_key_pressed(KeyCode::C) {
+                camera.reset();
+            }
+            brush.handle_keys(sim.attractions.size());
+        }
+        if !over_ui {
+            camera.handle_input();
+        }
+        let target = mouse_target(&camera).filter(|_| !over_ui);
+        brush.apply(&mut sim, target, &camera);
+        let steps = stepper.steps_for(get_frame_time());
+        let started = Instant::now();
+        for _ in 0..steps {
+            sim.step();
+        }
+        hud.record_steps(steps, started.elapsed().as_secs_f32());
+        camera.update_follow(&sim.population);
+        draw_world_edges(sim.params.boundary, &camera);
+        draw_particles(&sim.population, &colors, &camera);
+        draw_tracked(&sim.population, &camera);
+        overlay.draw(&sim.attractions, &colors);
         draw_fps();
+        draw_playback(&stepper);
+        brush.draw(&sim.params.field, &colors);
+        hud.draw(&sim, &colors);
+        if let Some(active) = &mut recorder {
+            if let Err(e) = capture_frame(active) {
+                eprintln!("Stopping recording: {}", e);
+                recorder = toggle_recording(recorder.take(), &record_path);
+            }
+        }
         next_frame().await
     }
 }
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use ::rand::distributions::{Distribution, Uniform};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Square matrix of attraction coefficients, where `get(from, to)` is how
+/// strongly species `from` is pulled toward species `to`. The same type holds
+/// the optional per-pair radius and beta overrides.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawMatrix")]
+pub struct AttractionMatrix {
+    size: usize,
+    values: Vec<f32>,
+}
+
+// Deserialised form, checked by `from_values` before it becomes a matrix
+#[derive(Deserialize)]
+struct RawMatrix {
+    size: usize,
+    values: Vec<f32>,
+}
+
+impl TryFrom<RawMatrix> for AttractionMatrix {
+    type Error = DimensionError;
+
+    fn try_from(raw: RawMatrix) -> Result<Self, Self::Error> {
+        AttractionMatrix::from_values(raw.size, raw.values)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DimensionError {
+    pub size: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for DimensionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {0}x{0} matrix needs {1} values, got {2}",
+            self.size,
+            self.size * self.size,
+            self.len
+        )
+    }
+}
+
+impl std::error::Error for DimensionError {}
+
+impl AttractionMatrix {
+    pub fn zeros(size: usize) -> Self {
+        AttractionMatrix {
+            size,
+            values: vec![0.; size * size],
+        }
+    }
+
+    /// Uniformly random coefficients in [-1, 1).
+    pub fn random(size: usize, rand_obj: &mut ChaCha8Rng) -> Self {
+        AttractionMatrix::random_in(size, -1., 1., rand_obj)
+    }
+
+    /// Uniformly random values in [low, high).
+    pub fn random_in(size: usize, low: f32, high: f32, rand_obj: &mut ChaCha8Rng) -> Self {
+        let dist = Uniform::from(low..high);
+        AttractionMatrix {
+            size,
+            values: (0..(size * size)).map(|_| dist.sample(rand_obj)).collect(),
+        }
+    }
+
+    /// Build from row-major values, checking there are exactly `size * size`.
+    pub fn from_values(size: usize, values: Vec<f32>) -> Result<Self, DimensionError> {
+        if values.len() != size * size {
+            return Err(DimensionError {
+                size,
+                len: values.len(),
+            });
+        }
+        Ok(AttractionMatrix { size, values })
+    }
+
+    /// Number of species.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn get(&self, from: usize, to: usize) -> f32 {
+        self.values[self.index(from, to)]
+    }
+
+    pub fn set(&mut self, from: usize, to: usize, value: f32) {
+        let i = self.index(from, to);
+        self.values[i] = value;
+    }
+
+    fn index(&self, from: usize, to: usize) -> usize {
+        assert!(
+            from < self.size && to < self.size,
+            "species pair ({}, {}) is out of range for {} species",
+            from,
+            to,
+            self.size
+        );
+        from * self.size + to
+    }
+}
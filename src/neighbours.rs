@@ -0,0 +1,167 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeighbourSearch {
+    BruteForce,
+    Grid,
+    KdTree,
+}
+
+impl NeighbourSearch {
+    pub fn next(self) -> Self {
+        match self {
+            NeighbourSearch::BruteForce => NeighbourSearch::Grid,
+            NeighbourSearch::Grid => NeighbourSearch::KdTree,
+            NeighbourSearch::KdTree => NeighbourSearch::BruteForce,
+        }
+    }
+}
+
+// A spatial index answers "which particles might be within `radius` of `point`?".
+// Candidates are returned as ascending indices so callers visit them in the same
+// order as a brute-force loop, keeping the summed forces bit-identical.
+pub trait SpatialIndex: Sync {
+    fn query(&self, point: Vec2, radius: f32, out: &mut Vec<usize>);
+}
+
+pub fn build_index(
+    search: NeighbourSearch,
+    positions: &[Vec2],
+    cell_size: f32,
+) -> Box<dyn SpatialIndex> {
+    match search {
+        NeighbourSearch::BruteForce => Box::new(BruteForce::new(positions)),
+        NeighbourSearch::Grid => Box::new(UniformGrid::new(positions, cell_size)),
+        NeighbourSearch::KdTree => Box::new(KdTree::new(positions)),
+    }
+}
+
+pub struct BruteForce {
+    len: usize,
+}
+
+impl BruteForce {
+    pub fn new(positions: &[Vec2]) -> Self {
+        BruteForce {
+            len: positions.len(),
+        }
+    }
+}
+
+impl SpatialIndex for BruteForce {
+    fn query(&self, _point: Vec2, _radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        out.extend(0..self.len);
+    }
+}
+
+pub struct UniformGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl UniformGrid {
+    pub fn new(positions: &[Vec2], cell_size: f32) -> Self {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, p) in positions.iter().enumerate() {
+            cells.entry(cell_of(*p, cell_size)).or_default().push(i);
+        }
+        UniformGrid { cell_size, cells }
+    }
+}
+
+fn cell_of(p: Vec2, cell_size: f32) -> (i32, i32) {
+    (
+        (p.x / cell_size).floor() as i32,
+        (p.y / cell_size).floor() as i32,
+    )
+}
+
+impl SpatialIndex for UniformGrid {
+    fn query(&self, point: Vec2, radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        // Cover the whole query box rather than assuming a 3x3 block, so rounding
+        // at cell edges can never drop a neighbour
+        let (x0, y0) = cell_of(point - Vec2::splat(radius), self.cell_size);
+        let (x1, y1) = cell_of(point + Vec2::splat(radius), self.cell_size);
+        for cx in x0..=x1 {
+            for cy in y0..=y1 {
+                if let Some(cell) = self.cells.get(&(cx, cy)) {
+                    out.extend_from_slice(cell);
+                }
+            }
+        }
+        out.sort_unstable();
+    }
+}
+
+// Static 2D k-d tree stored implicitly: each slice of `order` is split at its
+// midpoint on alternating axes.
+pub struct KdTree {
+    positions: Vec<Vec2>,
+    order: Vec<usize>,
+}
+
+impl KdTree {
+    pub fn new(positions: &[Vec2]) -> Self {
+        let mut order: Vec<usize> = (0..positions.len()).collect();
+        build_kd(&mut order, positions, 0);
+        KdTree {
+            positions: positions.to_vec(),
+            order,
+        }
+    }
+
+    fn query_range(
+        &self,
+        lo: usize,
+        hi: usize,
+        axis: usize,
+        min: Vec2,
+        max: Vec2,
+        out: &mut Vec<usize>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let i = self.order[mid];
+        let p = self.positions[i];
+        if p.cmpge(min).all() && p.cmple(max).all() {
+            out.push(i);
+        }
+        let split = p[axis];
+        if min[axis] <= split {
+            self.query_range(lo, mid, 1 - axis, min, max, out);
+        }
+        if max[axis] >= split {
+            self.query_range(mid + 1, hi, 1 - axis, min, max, out);
+        }
+    }
+}
+
+fn build_kd(order: &mut [usize], positions: &[Vec2], axis: usize) {
+    if order.len() <= 1 {
+        return;
+    }
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |a, b| {
+        positions[*a][axis].total_cmp(&positions[*b][axis])
+    });
+    let (left, right) = order.split_at_mut(mid);
+    build_kd(left, positions, 1 - axis);
+    build_kd(&mut right[1..], positions, 1 - axis);
+}
+
+impl SpatialIndex for KdTree {
+    fn query(&self, point: Vec2, radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        let min = point - Vec2::splat(radius);
+        let max = point + Vec2::splat(radius);
+        self.query_range(0, self.order.len(), 0, min, max, out);
+        out.sort_unstable();
+    }
+}
@@ -0,0 +1,133 @@
+use macroquad::prelude::*;
+use particle_life::AttractionMatrix;
+
+const CELL: f32 = 32.;
+const MARGIN: f32 = 10.;
+// Largest share of the window the overlay may cover; cells shrink to fit
+const MAX_SIDE: f32 = 0.6;
+// Values are only printed in cells at least this large
+const MIN_LABELLED_CELL: f32 = 24.;
+const CLICK_STEP: f32 = 0.1;
+const SCROLL_STEP: f32 = 0.05;
+
+// On-screen grid of the attraction matrix: rows are the species being pulled,
+// columns the species pulling them. Left/right click or scroll over a cell to
+// raise/lower its coefficient.
+pub struct MatrixOverlay {
+    pub visible: bool,
+}
+
+impl MatrixOverlay {
+    pub fn new() -> Self {
+        MatrixOverlay { visible: true }
+    }
+
+    fn cell(species: usize) -> f32 {
+        let max_side = MAX_SIDE * screen_width().min(screen_height());
+        (max_side / (species + 1) as f32).min(CELL)
+    }
+
+    // The header row/column of species swatches takes one extra cell
+    fn bounds(&self, species: usize) -> Rect {
+        let side = MatrixOverlay::cell(species) * (species + 1) as f32;
+        Rect::new(screen_width() - side - MARGIN, MARGIN, side, side)
+    }
+
+    fn cell_at(&self, point: Vec2, species: usize) -> Option<(usize, usize)> {
+        let bounds = self.bounds(species);
+        if !bounds.contains(point) {
+            return None;
+        }
+        let cell = MatrixOverlay::cell(species);
+        let col = ((point.x - bounds.x) / cell) as usize;
+        let row = ((point.y - bounds.y) / cell) as usize;
+        if row == 0 || col == 0 || row > species || col > species {
+            return None;
+        }
+        Some((row - 1, col - 1))
+    }
+
+    // Applies clicks and scrolls to the matrix. Returns true when the cursor is
+    // over the overlay, so the caller can skip other mouse handling.
+    pub fn handle_input(&self, matrix: &mut AttractionMatrix) -> bool {
+        let species = matrix.size();
+        let mouse = Vec2::from(mouse_position());
+        if !self.visible || !self.bounds(species).contains(mouse) {
+            return false;
+        }
+        if let Some((from, to)) = self.cell_at(mouse, species) {
+            let mut delta = 0.;
+            if is_mouse_button_pressed(MouseButton::Left) {
+                delta += CLICK_STEP;
+            }
+            if is_mouse_button_pressed(MouseButton::Right) {
+                delta -= CLICK_STEP;
+            }
+            let (_, scroll) = mouse_wheel();
+            if scroll != 0. {
+                delta += SCROLL_STEP * scroll.signum();
+            }
+            if delta != 0. {
+                let value = (matrix.get(from, to) + delta).clamp(-1., 1.);
+                matrix.set(from, to, value);
+            }
+        }
+        true
+    }
+
+    pub fn draw(&self, matrix: &AttractionMatrix, colors: &[Color]) {
+        if !self.visible {
+            return;
+        }
+        let species = matrix.size();
+        let bounds = self.bounds(species);
+        let cell = MatrixOverlay::cell(species);
+        let pad = cell / 8.;
+        draw_rectangle(
+            bounds.x,
+            bounds.y,
+            bounds.w,
+            bounds.h,
+            Color::new(0., 0., 0., 0.7),
+        );
+        for (i, &color) in colors.iter().enumerate().take(species) {
+            let offset = cell * (i + 1) as f32;
+            draw_rectangle(
+                bounds.x + offset + pad,
+                bounds.y + pad,
+                cell - 2. * pad,
+                cell - 2. * pad,
+                color,
+            );
+            draw_rectangle(
+                bounds.x + pad,
+                bounds.y + offset + pad,
+                cell - 2. * pad,
+                cell - 2. * pad,
+                color,
+            );
+        }
+        for from in 0..species {
+            for to in 0..species {
+                let value = matrix.get(from, to);
+                let x = bounds.x + cell * (to + 1) as f32;
+                let y = bounds.y + cell * (from + 1) as f32;
+                let fill = if value >= 0. {
+                    Color::new(0., value, 0., 1.)
+                } else {
+                    Color::new(-value, 0., 0., 1.)
+                };
+                draw_rectangle(x + 1., y + 1., cell - 2., cell - 2., fill);
+                if cell >= MIN_LABELLED_CELL {
+                    draw_text(
+                        &format!("{:.2}", value),
+                        x + 2.,
+                        y + cell / 2. + 4.,
+                        13.,
+                        WHITE,
+                    );
+                }
+            }
+        }
+    }
+}
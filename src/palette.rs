@@ -0,0 +1,96 @@
+/// Fewest species a simulation can be configured with.
+pub const MIN_SPECIES: usize = 2;
+/// Most species a simulation can be configured with.
+pub const MAX_SPECIES: usize = 32;
+
+// Colours are red, green, blue and alpha, each from 0 to 1, so they convert
+// straight into whichever colour type the front-end draws with
+const RED: [f32; 4] = [0.90, 0.16, 0.22, 1.00];
+const BLUE: [f32; 4] = [0.00, 0.47, 0.95, 1.00];
+const GREEN: [f32; 4] = [0.00, 0.89, 0.19, 1.00];
+const WHITE: [f32; 4] = [1.00, 1.00, 1.00, 1.00];
+const PINK: [f32; 4] = [1.00, 0.43, 0.76, 1.00];
+const YELLOW: [f32; 4] = [0.99, 0.98, 0.00, 1.00];
+const ORANGE: [f32; 4] = [1.00, 0.63, 0.00, 1.00];
+const PURPLE: [f32; 4] = [0.78, 0.48, 1.00, 1.00];
+const SKYBLUE: [f32; 4] = [0.40, 0.75, 1.00, 1.00];
+const LIME: [f32; 4] = [0.00, 0.62, 0.18, 1.00];
+
+/// Names accepted for `palette` in a config. `hsv` generates evenly spaced
+/// hues for any number of species; the others are fixed lists.
+pub const PRESETS: [&str; 5] = ["classic", "bright", "pastel", "earth", "hsv"];
+
+/// The colours of a fixed preset, or `None` for `hsv` and unknown names.
+pub fn preset(name: &str) -> Option<Vec<[f32; 4]>> {
+    let names: &[&str] = match name {
+        "classic" => &["red", "blue", "green", "white", "pink"],
+        "bright" => &[
+            "red", "orange", "yellow", "lime", "skyblue", "blue", "purple", "pink",
+        ],
+        "pastel" => &[
+            "#ffb3ba", "#ffdfba", "#ffffba", "#baffc9", "#bae1ff", "#d7baff", "#ffbaf2", "#e0e0e0",
+        ],
+        "earth" => &[
+            "#a0522d", "#d2b48c", "#556b2f", "#8fbc8f", "#daa520", "#708090",
+        ],
+        _ => return None,
+    };
+    Some(
+        names
+            .iter()
+            .map(|c| parse_color(c).expect("preset colours are valid"))
+            .collect(),
+    )
+}
+
+/// `count` colours with evenly spaced hues.
+pub fn hsv(count: usize) -> Vec<[f32; 4]> {
+    (0..count)
+        .map(|i| hsv_to_rgb(i as f32 / count as f32, 0.75, 0.96))
+        .collect()
+}
+
+// Hue, saturation and value all run from 0 to 1
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 4] {
+    let sector = h.fract() * 6.;
+    let f = sector.fract();
+    let p = v * (1. - s);
+    let q = v * (1. - s * f);
+    let t = v * (1. - s * (1. - f));
+    let (r, g, b) = match sector as u32 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    [r, g, b, 1.]
+}
+
+/// A colour name such as "red", or a hex code such as "#ff8800".
+pub fn parse_color(text: &str) -> Option<[f32; 4]> {
+    let named = match text.to_lowercase().as_str() {
+        "red" => Some(RED),
+        "blue" => Some(BLUE),
+        "green" => Some(GREEN),
+        "white" => Some(WHITE),
+        "pink" => Some(PINK),
+        "yellow" => Some(YELLOW),
+        "orange" => Some(ORANGE),
+        "purple" => Some(PURPLE),
+        "skyblue" => Some(SKYBLUE),
+        "lime" => Some(LIME),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let channel = |shift: u32| ((value >> shift) & 0xff) as f32 / 255.;
+    Some([channel(16), channel(8), channel(0), 1.])
+}
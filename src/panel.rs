@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+use macroquad::ui::{hash, root_ui, widgets};
+use particle_life::{Config, Simulation};
+
+const WIDTH: f32 = 340.;
+const HEIGHT: f32 = 200.;
+const MAX_PARTICLES: f32 = 20000.;
+
+// Sliders for the main simulation constants, applied to the running
+// simulation as they move
+pub struct ParamPanel {
+    pub visible: bool,
+    // The sliders' edit boxes keep focus from a click on the panel until the
+    // next click elsewhere, so track it here; the UI does not expose it
+    focused: bool,
+    hovered: bool,
+    // Set by the click that takes focus away from the panel and held until
+    // the button is released, so that click only does that
+    dismissing: bool,
+}
+
+impl ParamPanel {
+    pub fn new() -> Self {
+        ParamPanel {
+            visible: false,
+            focused: false,
+            hovered: false,
+            dismissing: false,
+        }
+    }
+
+    // True while typing could be going into the panel, when hotkeys must not
+    // also react to the same keys
+    pub fn wants_keyboard(&self) -> bool {
+        self.visible && (self.hovered || self.focused)
+    }
+
+    // Draws the panel and applies any changes. Returns true when the cursor is
+    // over the panel or the click leaving it is held, so those clicks do not
+    // reach the world
+    pub fn update(
+        &mut self,
+        sim: &mut Simulation,
+        config: &mut Config,
+        path: Option<&Path>,
+    ) -> bool {
+        if !self.visible {
+            self.focused = false;
+            self.hovered = false;
+            self.dismissing = false;
+            return false;
+        }
+        let params = &mut sim.params;
+        let mut particles = sim.population.len() as f32;
+        let mut save = false;
+        let position = vec2(10., screen_height() - HEIGHT - 10.);
+        widgets::Window::new(hash!(), position, vec2(WIDTH, HEIGHT))
+            .label("Parameters")
+            .ui(&mut root_ui(), |ui| {
+                ui.slider(hash!(), "radius", 0.01..0.5, &mut params.max_radius);
+                ui.slider(hash!(), "time step", 0.001..0.05, &mut params.time_step);
+                ui.slider(
+                    hash!(),
+                    "friction half-life",
+                    0.005..0.5,
+                    &mut params.friction_half_life,
+                );
+                ui.slider(hash!(), "beta", 0.01..0.99, &mut params.beta);
+                ui.slider(hash!(), "centre pull", 0.0..0.1, &mut params.centre_pull);
+                ui.slider(hash!(), "particles", 1.0..MAX_PARTICLES, &mut particles);
+                save = ui.button(None, "Save to config");
+            });
+        let particles = (particles.round() as usize).max(1);
+        if particles != sim.population.len() {
+            sim.set_particle_count(particles);
+        }
+        if save {
+            save_config(sim, config, path);
+        }
+        self.hovered = root_ui().is_mouse_over(Vec2::from(mouse_position()));
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.dismissing = self.focused && !self.hovered;
+            self.focused = self.hovered;
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.dismissing = false;
+        }
+        self.hovered || self.dismissing
+    }
+}
+
+// Writes back to the file the config came from, or a new one if there was none
+fn save_config(sim: &Simulation, config: &mut Config, path: Option<&Path>) {
+    config.set_params(&sim.params);
+    let path = path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(format!("config-{}.toml", crate::timestamp())));
+    match config.save(&path) {
+        Ok(()) => eprintln!("Saved config to {}", path.display()),
+        Err(e) => eprintln!("{}", e),
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+
+/// Output formats for recorded frames, picked from the output path: `.gif`
+/// for an animated GIF, `-` (stdout) or `.rgba` for a raw RGBA8 stream that
+/// can be piped into an encoder such as ffmpeg, anything else is a directory
+/// of numbered PNGs.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RecordFormat {
+    PngSequence,
+    Gif,
+    Raw,
+}
+
+impl RecordFormat {
+    pub fn from_path(path: &Path) -> Self {
+        if path == Path::new("-") {
+            return RecordFormat::Raw;
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gif") => RecordFormat::Gif,
+            Some("rgba") | Some("raw") => RecordFormat::Raw,
+            _ => RecordFormat::PngSequence,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(PathBuf, std::io::Error),
+    Image(PathBuf, image::ImageError),
+    FrameSize {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Io(path, e) => write!(f, "could not write {}: {}", path.display(), e),
+            RecordError::Image(path, e) => write!(f, "could not encode {}: {}", path.display(), e),
+            RecordError::FrameSize { expected, found } => write!(
+                f,
+                "frame is {}x{} but the recording is {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+enum Sink {
+    Png,
+    Gif(Box<GifEncoder<BufWriter<File>>>),
+    Raw(Box<dyn Write + Send>),
+}
+
+/// Writes a stream of equally sized frames in one of the `RecordFormat`s.
+pub struct Recorder {
+    path: PathBuf,
+    sink: Sink,
+    frame_delay_ms: u32,
+    size: Option<(u32, u32)>,
+    frames: usize,
+}
+
+impl Recorder {
+    /// Start a recording at `path`, playing back at `fps` frames per second
+    /// where the format stores timing.
+    pub fn start(path: &Path, fps: u32) -> Result<Self, RecordError> {
+        let io_err = |e| RecordError::Io(path.to_path_buf(), e);
+        let sink = match RecordFormat::from_path(path) {
+            RecordFormat::PngSequence => {
+                std::fs::create_dir_all(path).map_err(io_err)?;
+                Sink::Png
+            }
+            RecordFormat::Gif => {
+                let file = BufWriter::new(File::create(path).map_err(io_err)?);
+                let mut encoder = GifEncoder::new(file);
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(|e| RecordError::Image(path.to_path_buf(), e))?;
+                Sink::Gif(Box::new(encoder))
+            }
+            RecordFormat::Raw if path == Path::new("-") => {
+                Sink::Raw(Box::new(BufWriter::new(std::io::stdout())))
+            }
+            RecordFormat::Raw => Sink::Raw(Box::new(BufWriter::new(
+                File::create(path).map_err(io_err)?,
+            ))),
+        };
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            sink,
+            frame_delay_ms: 1000 / fps.max(1),
+            size: None,
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn push(&mut self, frame: RgbaImage) -> Result<(), RecordError> {
+        let found = frame.dimensions();
+        let expected = *self.size.get_or_insert(found);
+        if found != expected {
+            return Err(RecordError::FrameSize { expected, found });
+        }
+        match &mut self.sink {
+            Sink::Png => {
+                let file = self.path.join(format!("frame-{:06}.png", self.frames));
+                frame
+                    .save(&file)
+                    .map_err(|e| RecordError::Image(file.clone(), e))?;
+            }
+            Sink::Gif(encoder) => {
+                let delay = Delay::from_numer_denom_ms(self.frame_delay_ms, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(frame, 0, 0, delay))
+                    .map_err(|e| RecordError::Image(self.path.clone(), e))?;
+            }
+            Sink::Raw(out) => out
+                .write_all(frame.as_raw())
+                .map_err(|e| RecordError::Io(self.path.clone(), e))?,
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Flush everything to disk. The GIF trailer is written when the encoder
+    /// is dropped here.
+    pub fn finish(self) -> Result<(), RecordError> {
+        match self.sink {
+            Sink::Raw(mut out) => out.flush().map_err(|e| RecordError::Io(self.path, e)),
+            Sink::Png | Sink::Gif(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use glam::{vec2, Vec2};
+use image::{Rgba, RgbaImage};
+
+use crate::simulation::Particle;
+
+/// The part of the world to draw. At zoom 1 the unit square fills the shorter
+/// side of the image, centred on `centre`.
+#[derive(Clone, Copy, Debug)]
+pub struct View {
+    pub centre: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            centre: vec2(0.5, 0.5),
+            zoom: 1.,
+        }
+    }
+}
+
+// Particles are 2px in the 800px window; keep that proportion at any size
+const REFERENCE_SIZE: f32 = 800.;
+const REFERENCE_RADIUS: f32 = 2.;
+
+/// Draw the population into a new image on the CPU, so frames can be
+/// produced without a window and at any resolution.
+pub fn render(
+    population: &[Particle],
+    colors: &[[f32; 4]],
+    view: View,
+    width: u32,
+    height: u32,
+) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    let scale = view.zoom * width.min(height) as f32;
+    let screen_centre = vec2(width as f32, height as f32) / 2.;
+    let radius = (REFERENCE_RADIUS * scale / REFERENCE_SIZE).max(0.75);
+    let palette: Vec<Rgba<u8>> = colors
+        .iter()
+        .map(|c| Rgba(c.map(|channel| (channel * 255.) as u8)))
+        .collect();
+    for p in population {
+        let centre = (p.position - view.centre) * scale + screen_centre;
+        fill_circle(&mut image, centre, radius, palette[p.color]);
+    }
+    image
+}
+
+fn fill_circle(image: &mut RgbaImage, centre: Vec2, radius: f32, color: Rgba<u8>) {
+    if !centre.is_finite() {
+        return;
+    }
+    let x0 = (centre.x - radius).floor().max(0.) as u32;
+    let y0 = (centre.y - radius).floor().max(0.) as u32;
+    let x1 = ((centre.x + radius).ceil().max(0.) as u32).min(image.width());
+    let y1 = ((centre.y + radius).ceil().max(0.) as u32).min(image.height());
+    for y in y0..y1 {
+        for x in x0..x1 {
+            // Sample at pixel centres
+            let offset = vec2(x as f32 + 0.5, y as f32 + 0.5) - centre;
+            if offset.length_squared() <= radius * radius {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use ::rand::distributions::WeightedIndex;
+use ::rand::prelude::*;
+use glam::{vec2, Vec2};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::boundary::Boundary;
+use crate::field::ForceField;
+use crate::integrator::Integrator;
+use crate::kernel::{ForceKernel, KernelKind};
+use crate::matrix::AttractionMatrix;
+use crate::neighbours::{build_index, NeighbourSearch, SpatialIndex};
+use crate::spawn::{DensityMap, Layout, Placement};
+use crate::species::{ResolvedSpecies, Species};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Particle {
+    /// Unique within a simulation and kept for the particle's lifetime
+    pub id: u32,
+    pub color: usize,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Physical constants shared by every step of a simulation.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Params {
+    pub seed: u64,
+    pub max_radius: f32,
+    pub time_step: f32,
+    pub friction_half_life: f32,
+    pub beta: f32,
+    pub num_particles: usize,
+    pub search: NeighbourSearch,
+    pub boundary: Boundary,
+    pub centre_pull: f32,
+    pub kernel: KernelKind,
+    /// When set, per-pair radii are drawn uniformly from this range
+    pub pair_radius_range: Option<[f32; 2]>,
+    /// When set, per-pair betas are drawn uniformly from this range
+    pub pair_beta_range: Option<[f32; 2]>,
+    pub integrator: Integrator,
+    /// Initial placement of particles, also used when resetting
+    pub layout: Layout,
+    /// Settings for the force field tool
+    pub field: ForceField,
+    /// When set, steps are split into substeps so no particle moves further
+    /// than this in one
+    pub max_displacement: Option<f32>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            seed: 50,
+            max_radius: 0.1,
+            time_step: 0.01,
+            friction_half_life: 0.04,
+            beta: 0.3,
+            num_particles: 4000,
+            search: NeighbourSearch::Grid,
+            boundary: Boundary::CentrePull,
+            centre_pull: 1. / 128.,
+            kernel: KernelKind::Linear,
+            pair_radius_range: None,
+            pair_beta_range: None,
+            integrator: Integrator::SemiImplicitEuler,
+            layout: Layout::Uniform,
+            field: ForceField::default(),
+            max_displacement: None,
+        }
+    }
+}
+
+impl Params {
+    /// Describes the first out-of-range value, if any.
+    pub fn check(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f32| {
+            if value > 0. && value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("{} must be a positive number, got {}", name, value))
+            }
+        };
+        positive("max_radius", self.max_radius)?;
+        positive("time_step", self.time_step)?;
+        positive("friction_half_life", self.friction_half_life)?;
+        if !(self.beta > 0. && self.beta < 1.) {
+            return Err(format!(
+                "beta must be between 0 and 1 (exclusive), got {}",
+                self.beta
+            ));
+        }
+        if !(self.centre_pull >= 0. && self.centre_pull.is_finite()) {
+            return Err(format!(
+                "centre_pull must be zero or positive, got {}",
+                self.centre_pull
+            ));
+        }
+        if let Some(cap) = self.max_displacement {
+            positive("max_displacement", cap)?;
+        }
+        if let Some([low, high]) = self.pair_beta_range {
+            if !(low > 0. && low < high && high < 1.) {
+                return Err(format!(
+                    "pair_beta_range must satisfy 0 < low < high < 1, got [{}, {}]",
+                    low, high
+                ));
+            }
+        }
+        if let Some([low, high]) = self.pair_radius_range {
+            if !(low > 0. && low < high && high.is_finite()) {
+                return Err(format!(
+                    "pair_radius_range must satisfy 0 < low < high, got [{}, {}]",
+                    low, high
+                ));
+            }
+        }
+        self.field.check().map_err(|e| format!("field: {}", e))
+    }
+}
+
+/// A particle population together with everything needed to advance it.
+pub struct Simulation {
+    pub population: Vec<Particle>,
+    pub attractions: AttractionMatrix,
+    /// Per-pair repulsion distances overriding the species beta, if set
+    pub betas: Option<AttractionMatrix>,
+    /// Per-pair interaction radii overriding the species radius, if set
+    pub radii: Option<AttractionMatrix>,
+    /// One entry per colour, indexed like the attraction matrix
+    pub species: Vec<Species>,
+    pub params: Params,
+    pub rng: ChaCha8Rng,
+    /// Steps taken since the simulation was created
+    pub steps: u64,
+    /// Simulated time since the simulation was created
+    pub time: f64,
+    /// Id for the next particle added. Ids are never handed out twice, even
+    /// after the particle holding one is removed
+    pub next_id: u32,
+    /// Image sampled by `Layout::Image`, if one was loaded
+    pub density: Option<DensityMap>,
+    /// External force applied during every step, centred on the given point
+    pub active_field: Option<(Vec2, ForceField)>,
+}
+
+impl Simulation {
+    pub fn new(params: Params, num_colors: usize) -> Self {
+        Simulation::with_species(params, vec![Species::default(); num_colors], None)
+    }
+
+    /// A simulation with one entry in `species` per colour, laid out according
+    /// to `params.layout` and the species proportions.
+    pub fn with_species(
+        params: Params,
+        species: Vec<Species>,
+        density: Option<DensityMap>,
+    ) -> Self {
+        // Every random draw comes from this one generator, so a seed fully
+        // determines the run
+        let mut rng = ChaCha8Rng::seed_from_u64(params.seed);
+        let attractions = AttractionMatrix::random(species.len(), &mut rng);
+        let population = generate_population(
+            params.num_particles,
+            &species,
+            params.layout,
+            density.as_ref(),
+            &mut rng,
+        );
+        let mut sim = Simulation {
+            population,
+            attractions,
+            betas: None,
+            radii: None,
+            species,
+            params,
+            rng,
+            steps: 0,
+            time: 0.,
+            next_id: params.num_particles as u32,
+            density,
+            active_field: None,
+        };
+        sim.randomise_pair_overrides();
+        sim
+    }
+
+    /// Replace the population with a freshly generated one, keeping the
+    /// attraction matrix and parameters. The new particles get fresh ids.
+    pub fn reset_population(&mut self) {
+        let ids = self.take_ids(self.params.num_particles);
+        self.population = generate_population(
+            self.params.num_particles,
+            &self.species,
+            self.params.layout,
+            self.density.as_ref(),
+            &mut self.rng,
+        )
+        .into_iter()
+        .zip(ids)
+        .map(|(p, id)| Particle { id, ..p })
+        .collect();
+        self.steps = 0;
+        self.time = 0.;
+    }
+
+    /// Advance the population by one time step.
+    pub fn step(&mut self) {
+        self.population = update_population(self);
+        self.steps += 1;
+        self.time += self.params.time_step as f64;
+    }
+
+    /// Add or remove particles until there are `count`. New particles are
+    /// placed at random with fresh ids; the most recently added are removed
+    /// first.
+    pub fn set_particle_count(&mut self, count: usize) {
+        let len = self.population.len();
+        if count <= len {
+            self.population.truncate(count);
+        } else {
+            let ids = self.take_ids(count - len);
+            let added = generate_population(
+                count - len,
+                &self.species,
+                self.params.layout,
+                self.density.as_ref(),
+                &mut self.rng,
+            );
+            self.population.extend(
+                added
+                    .into_iter()
+                    .zip(ids)
+                    .map(|(p, id)| Particle { id, ..p }),
+            );
+        }
+        self.params.num_particles = count;
+    }
+
+    /// Number of particles of each species.
+    pub fn species_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.attractions.size()];
+        for p in &self.population {
+            counts[p.color] += 1;
+        }
+        counts
+    }
+
+    /// Mean kinetic energy per particle, using each species' mass.
+    pub fn kinetic_energy(&self) -> f32 {
+        if self.population.is_empty() {
+            return 0.;
+        }
+        let total: f32 = self
+            .population
+            .iter()
+            .map(|p| 0.5 * self.species[p.color].mass * p.velocity.length_squared())
+            .sum();
+        total / self.population.len() as f32
+    }
+
+    /// Change how the world's edges behave, bringing every particle inside the
+    /// new boundary at once so no step sees positions it would not allow.
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.params.boundary = boundary;
+        for p in &mut self.population {
+            boundary.confine(p);
+        }
+    }
+
+    /// Replace the attraction matrix with a fresh random one, along with any
+    /// per-pair radii and betas that have a random range configured.
+    pub fn randomise_attractions(&mut self) {
+        self.attractions = AttractionMatrix::random(self.attractions.size(), &mut self.rng);
+        self.randomise_pair_overrides();
+    }
+
+    fn randomise_pair_overrides(&mut self) {
+        let size = self.attractions.size();
+        if let Some([low, high]) = self.params.pair_radius_range {
+            self.radii = Some(AttractionMatrix::random_in(size, low, high, &mut self.rng));
+        }
+        if let Some([low, high]) = self.params.pair_beta_range {
+            self.betas = Some(AttractionMatrix::random_in(size, low, high, &mut self.rng));
+        }
+    }
+
+    /// Add `count` particles of `species` scattered over a disc, with fresh
+    /// ids.
+    pub fn spawn_within(&mut self, centre: Vec2, radius: f32, species: usize, count: usize) {
+        for id in self.take_ids(count) {
+            let r = radius * self.rng.gen_range(0. ..1_f32).sqrt();
+            let angle = self.rng.gen_range(0. ..std::f32::consts::TAU);
+            let mut p = Particle {
+                id,
+                color: species,
+                position: centre + vec2(angle.cos(), angle.sin()) * r,
+                velocity: Vec2::ZERO,
+            };
+            self.params.boundary.confine(&mut p);
+            self.population.push(p);
+        }
+        self.params.num_particles = self.population.len();
+    }
+
+    // Reserve `count` ids that no particle has had before
+    fn take_ids(&mut self, count: usize) -> std::ops::Range<u32> {
+        let first = self.next_id;
+        self.next_id += count as u32;
+        first..self.next_id
+    }
+
+    /// Remove every particle within `radius` of `centre`, returning how many
+    /// were removed.
+    pub fn erase_within(&mut self, centre: Vec2, radius: f32) -> usize {
+        let boundary = self.params.boundary;
+        let before = self.population.len();
+        self.population
+            .retain(|p| boundary.offset(p.position, centre).length() >= radius);
+        self.params.num_particles = self.population.len();
+        before - self.population.len()
+    }
+}
+
+pub fn generate_population(
+    num_particles: usize,
+    species: &[Species],
+    layout: Layout,
+    density: Option<&DensityMap>,
+    rand_obj: &mut ChaCha8Rng,
+) -> Vec<Particle> {
+    let proportions: Vec<f32> = species.iter().map(|s| s.proportion).collect();
+    // Equal shares keep the plain uniform draw, so existing seeds reproduce.
+    // Proportions `check_all` would reject fall back to equal shares too
+    let weighted = if proportions.windows(2).all(|w| w[0] == w[1]) {
+        None
+    } else {
+        WeightedIndex::new(&proportions).ok()
+    };
+    let placement = Placement {
+        layout,
+        count: num_particles,
+        num_species: species.len(),
+        density,
+    };
+    (0..num_particles)
+        .map(|id| {
+            let color = match &weighted {
+                Some(weighted) => weighted.sample(rand_obj),
+                None => rand_obj.gen_range(0..species.len()),
+            };
+            let position = placement.place(id, color, rand_obj);
+            Particle {
+                id: id as u32,
+                color,
+                position,
+                velocity: Vec2::ZERO,
+            }
+        })
+        .collect()
+}
+
+// Candidate neighbours of `point`, including those seen across wrapped edges
+fn query_neighbours(
+    index: &dyn SpatialIndex,
+    boundary: Boundary,
+    point: Vec2,
+    radius: f32,
+    out: &mut Vec<usize>,
+    scratch: &mut Vec<usize>,
+) {
+    if boundary != Boundary::Wrap {
+        index.query(point, radius, out);
+        return;
+    }
+    out.clear();
+    for image in boundary.images(point, radius) {
+        index.query(image, radius, scratch);
+        out.extend_from_slice(scratch);
+    }
+    out.sort_unstable();
+    out.dedup();
+}
+
+// Everything the force calculation needs besides the particles themselves,
+// resolved once per step
+struct Interactions<'a> {
+    attractions: &'a AttractionMatrix,
+    betas: Option<&'a AttractionMatrix>,
+    radii: Option<&'a AttractionMatrix>,
+    species: Vec<ResolvedSpecies>,
+    kernel: &'a dyn ForceKernel,
+    field: Option<(Vec2, ForceField)>,
+    params: &'a Params,
+    // Each colour must search out to the furthest it can see
+    query_radii: Vec<f32>,
+}
+
+impl<'a> Interactions<'a> {
+    fn new(sim: &'a Simulation) -> Self {
+        let params = &sim.params;
+        let mut interactions = Interactions {
+            attractions: &sim.attractions,
+            betas: sim.betas.as_ref(),
+            radii: sim.radii.as_ref(),
+            species: sim.species.iter().map(|s| s.resolve(params)).collect(),
+            kernel: params.kernel.kernel(),
+            field: sim.active_field,
+            params,
+            query_radii: Vec::new(),
+        };
+        interactions.query_radii = (0..interactions.species.len())
+            .map(|from| {
+                (0..interactions.species.len())
+                    .map(|to| interactions.pair_radius(from, to))
+                    .fold(0., f32::max)
+            })
+            .collect();
+        interactions
+    }
+
+    fn pair_radius(&self, from: usize, to: usize) -> f32 {
+        self.radii
+            .map_or(self.species[from].radius, |r| r.get(from, to))
+    }
+
+    // Acceleration of every particle from its neighbours and the boundary
+    fn accelerations(&self, population: &[Particle]) -> Vec<Vec2> {
+        let params = self.params;
+        let boundary = params.boundary;
+        // The index must cover the furthest any colour can see
+        let max_radius = self.query_radii.iter().copied().fold(0., f32::max);
+        let positions: Vec<Vec2> = population.iter().map(|p| p.position).collect();
+        let index = build_index(params.search, &positions, max_radius);
+        // The centre pull is a velocity change per nominal time step
+        let pull_strength = params.centre_pull / params.time_step;
+        population
+            .par_iter()
+            .map_init(
+                || (Vec::new(), Vec::new()),
+                |(neighbours, scratch), p1| {
+                    let s = &self.species[p1.color];
+                    let mut total_force = Vec2::ZERO;
+                    query_neighbours(
+                        index.as_ref(),
+                        boundary,
+                        p1.position,
+                        self.query_radii[p1.color],
+                        neighbours,
+                        scratch,
+                    );
+                    for p2 in neighbours.iter().map(|&j| &population[j]) {
+                        if p1.id == p2.id {
+                            continue;
+                        };
+                        let radius = self.pair_radius(p1.color, p2.color);
+                        let offset = boundary.offset(p1.position, p2.position);
+                        let distance = offset.length();
+                        if (distance > 0.) & (distance < radius) {
+                            let beta = self.betas.map_or(s.beta, |b| b.get(p1.color, p2.color));
+                            let f = self.kernel.force(
+                                distance / radius,
+                                self.attractions.get(p1.color, p2.color),
+                                beta,
+                            );
+                            // Forces scale with the radius they act over
+                            total_force += (offset / distance) * (f * radius);
+                        }
+                    }
+                    let external = self.field.map_or(Vec2::ZERO, |(centre, field)| {
+                        field.acceleration(boundary.offset(p1.position, centre))
+                    });
+                    total_force / s.mass + boundary.pull(p1.position, pull_strength) + external
+                },
+            )
+            .collect()
+    }
+}
+
+// Substeps never get shorter than this fraction of the time step, so a single
+// runaway particle cannot stall the simulation
+const MAX_SUBSTEPS: f32 = 64.;
+
+/// The simulation's population one time step later.
+pub fn update_population(sim: &Simulation) -> Vec<Particle> {
+    let interactions = Interactions::new(sim);
+    let params = &sim.params;
+    let mut state = sim.population.clone();
+    let mut remaining = params.time_step;
+    while remaining > 0. {
+        let dt = match params.max_displacement {
+            Some(cap) => {
+                // Split the step wherever the fastest particle would move
+                // further than the cap
+                let max_speed = state.iter().map(|p| p.velocity.length()).fold(0., f32::max);
+                if max_speed * remaining <= cap {
+                    remaining
+                } else {
+                    (cap / max_speed)
+                        .max(params.time_step / MAX_SUBSTEPS)
+                        .min(remaining)
+                }
+            }
+            None => remaining,
+        };
+        state = params.integrator.advance(
+            &state,
+            dt,
+            |p| interactions.species[p.color].friction_factor(dt),
+            |state| interactions.accelerations(state),
+        );
+        for p in state.iter_mut() {
+            params.boundary.confine(p);
+        }
+        remaining -= dt;
+    }
+    state
+}
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use glam::Vec2;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::matrix::AttractionMatrix;
+use crate::simulation::{Params, Particle, Simulation};
+use crate::species::{self, Species};
+
+/// Bumped whenever the snapshot layout changes incompatibly.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything needed to resume a simulation exactly where it left off.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub params: Params,
+    pub attractions: AttractionMatrix,
+    #[serde(default)]
+    pub betas: Option<AttractionMatrix>,
+    #[serde(default)]
+    pub radii: Option<AttractionMatrix>,
+    #[serde(default)]
+    pub species: Vec<Species>,
+    pub rng: ChaCha8Rng,
+    #[serde(default)]
+    pub steps: u64,
+    #[serde(default)]
+    pub time: f64,
+    /// Missing from snapshots taken before the id counter was stored
+    #[serde(default)]
+    pub next_id: Option<u32>,
+    pub particles: Vec<ParticleRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParticleRecord {
+    /// Missing from snapshots taken before particles had ids
+    #[serde(default)]
+    pub id: Option<u32>,
+    pub color: usize,
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(PathBuf, std::io::Error),
+    Format(PathBuf, serde_json::Error),
+    Version(PathBuf, u32),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(path, e) => write!(f, "could not access {}: {}", path.display(), e),
+            SnapshotError::Format(path, e) => {
+                write!(f, "{} is not a valid snapshot: {}", path.display(), e)
+            }
+            SnapshotError::Version(path, v) => write!(
+                f,
+                "{} has snapshot version {}, but only version {} is supported",
+                path.display(),
+                v,
+                SNAPSHOT_VERSION
+            ),
+            SnapshotError::Invalid(path, msg) => {
+                write!(f, "{} is not a valid snapshot: {}", path.display(), msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Snapshot {
+    pub fn capture(sim: &Simulation) -> Self {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            params: sim.params,
+            attractions: sim.attractions.clone(),
+            betas: sim.betas.clone(),
+            radii: sim.radii.clone(),
+            species: sim.species.clone(),
+            rng: sim.rng.clone(),
+            steps: sim.steps,
+            time: sim.time,
+            next_id: Some(sim.next_id),
+            particles: sim
+                .population
+                .iter()
+                .map(|p| ParticleRecord {
+                    id: Some(p.id),
+                    color: p.color,
+                    position: p.position.into(),
+                    velocity: p.velocity.into(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn restore(self) -> Simulation {
+        // Snapshots from before per-species parameters get the defaults
+        let species = if self.species.is_empty() {
+            vec![Species::default(); self.attractions.size()]
+        } else {
+            self.species
+        };
+        let next_id = self.next_id.unwrap_or_else(|| {
+            let ids = self.particles.iter().enumerate();
+            ids.map(|(i, p)| p.id.unwrap_or(i as u32) + 1)
+                .max()
+                .unwrap_or(0)
+        });
+        Simulation {
+            population: self
+                .particles
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| Particle {
+                    id: p.id.unwrap_or(i as u32),
+                    color: p.color,
+                    position: Vec2::from(p.position),
+                    velocity: Vec2::from(p.velocity),
+                })
+                .collect(),
+            attractions: self.attractions,
+            betas: self.betas,
+            radii: self.radii,
+            species,
+            params: self.params,
+            rng: self.rng,
+            steps: self.steps,
+            time: self.time,
+            next_id,
+            density: None,
+            active_field: None,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        let io_err = |e| SnapshotError::Io(path.to_path_buf(), e);
+        let mut file = BufWriter::new(File::create(path).map_err(io_err)?);
+        serde_json::to_writer(&mut file, self)
+            .map_err(|e| SnapshotError::Format(path.to_path_buf(), e))?;
+        file.flush().map_err(io_err)
+    }
+
+    pub fn load(path: &Path) -> Result<Snapshot, SnapshotError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| SnapshotError::Io(path.to_path_buf(), e))?;
+        let format_err = |e| SnapshotError::Format(path.to_path_buf(), e);
+        // Check the version first so an old file gets a clear message rather
+        // than a missing-field error
+        let probe: VersionProbe = serde_json::from_str(&text).map_err(format_err)?;
+        if probe.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(path.to_path_buf(), probe.version));
+        }
+        let snapshot: Snapshot = serde_json::from_str(&text).map_err(format_err)?;
+        let species = snapshot.attractions.size();
+        if let Some(p) = snapshot.particles.iter().find(|p| p.color >= species) {
+            return Err(SnapshotError::Invalid(
+                path.to_path_buf(),
+                format!(
+                    "particle has species {} but the attraction matrix only has {}",
+                    p.color, species
+                ),
+            ));
+        }
+        let mut ids = HashSet::new();
+        if let Some(id) = snapshot
+            .particles
+            .iter()
+            .filter_map(|p| p.id)
+            .find(|id| !ids.insert(*id))
+        {
+            return Err(SnapshotError::Invalid(
+                path.to_path_buf(),
+                format!("particle id {} is used more than once", id),
+            ));
+        }
+        if let Some(next_id) = snapshot.next_id {
+            if let Some(id) = ids.iter().find(|id| **id >= next_id) {
+                return Err(SnapshotError::Invalid(
+                    path.to_path_buf(),
+                    format!("particle id {} is not below the next id {}", id, next_id),
+                ));
+            }
+        }
+        for (name, matrix) in [("beta", &snapshot.betas), ("radius", &snapshot.radii)] {
+            if let Some(matrix) = matrix {
+                if matrix.size() != species {
+                    return Err(SnapshotError::Invalid(
+                        path.to_path_buf(),
+                        format!(
+                            "{} matrix covers {} species but the attraction matrix has {}",
+                            name,
+                            matrix.size(),
+                            species
+                        ),
+                    ));
+                }
+            }
+        }
+        let invalid = |msg| SnapshotError::Invalid(path.to_path_buf(), msg);
+        snapshot.params.check().map_err(invalid)?;
+        species::check_all(&snapshot.species).map_err(invalid)?;
+        if let Some(beta) = snapshot
+            .betas
+            .iter()
+            .flat_map(|m| m.values())
+            .find(|b| !(**b > 0. && **b < 1.))
+        {
+            return Err(invalid(format!(
+                "pair betas must all be between 0 and 1 (exclusive), got {}",
+                beta
+            )));
+        }
+        if let Some(radius) = snapshot
+            .radii
+            .iter()
+            .flat_map(|m| m.values())
+            .find(|r| !(**r > 0. && r.is_finite()))
+        {
+            return Err(invalid(format!(
+                "pair radii must all be positive numbers, got {}",
+                radius
+            )));
+        }
+        if !snapshot.species.is_empty() && snapshot.species.len() != species {
+            return Err(SnapshotError::Invalid(
+                path.to_path_buf(),
+                format!(
+                    "{} species parameter sets for {} species",
+                    snapshot.species.len(),
+                    species
+                ),
+            ));
+        }
+        Ok(snapshot)
+    }
+}
+
+pub fn save(sim: &Simulation, path: &Path) -> Result<(), SnapshotError> {
+    Snapshot::capture(sim).save(path)
+}
+
+pub fn load(path: &Path) -> Result<Simulation, SnapshotError> {
+    Snapshot::load(path).map(Snapshot::restore)
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::TAU;
+use std::path::Path;
+
+use ::rand::prelude::*;
+use glam::{vec2, Vec2};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Where the initial particles are placed in the unit square.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// Scattered evenly over the whole square
+    Uniform,
+    /// Filled disc in the middle of the square
+    Disc,
+    /// Thin annulus around the middle
+    Ring,
+    /// One Gaussian cluster per species, spaced around a circle
+    Blobs,
+    /// One vertical band per species
+    Stripes,
+    /// One pie slice of a central disc per species
+    Sectors,
+    /// A regular lattice, with species assigned at random
+    Grid,
+    /// Drawn from the brightness of an image, brighter pixels more likely
+    Image,
+}
+
+impl Layout {
+    pub fn next(self) -> Self {
+        match self {
+            Layout::Uniform => Layout::Disc,
+            Layout::Disc => Layout::Ring,
+            Layout::Ring => Layout::Blobs,
+            Layout::Blobs => Layout::Stripes,
+            Layout::Stripes => Layout::Sectors,
+            Layout::Sectors => Layout::Grid,
+            Layout::Grid => Layout::Image,
+            Layout::Image => Layout::Uniform,
+        }
+    }
+}
+
+const CENTRE: Vec2 = Vec2::new(0.5, 0.5);
+const DISC_RADIUS: f32 = 0.4;
+const RING_WIDTH: f32 = 0.05;
+const BLOB_CIRCLE: f32 = 0.3;
+const BLOB_SIGMA: f32 = 0.05;
+
+/// Pixel brightness of an image, used as a sampling density for
+/// `Layout::Image`. The image is fitted inside the unit square keeping its
+/// aspect ratio.
+#[derive(Clone, Debug)]
+pub struct DensityMap {
+    width: u32,
+    height: u32,
+    weights: Vec<f32>,
+    max: f32,
+}
+
+impl DensityMap {
+    pub fn load(path: &Path) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.into_luma8();
+        let weights: Vec<f32> = image.pixels().map(|p| p.0[0] as f32 / 255.).collect();
+        let max = weights.iter().copied().fold(0., f32::max);
+        Ok(DensityMap {
+            width: image.width(),
+            height: image.height(),
+            weights,
+            max,
+        })
+    }
+
+    /// True when no pixel has any brightness to sample from.
+    pub fn is_empty(&self) -> bool {
+        self.max <= 0.
+    }
+
+    // Rejection sampling: pick pixels uniformly and keep them in proportion
+    // to their brightness
+    fn sample(&self, rng: &mut ChaCha8Rng) -> Vec2 {
+        let scale = 1. / self.width.max(self.height) as f32;
+        let origin = CENTRE - vec2(self.width as f32, self.height as f32) * scale / 2.;
+        loop {
+            let x = rng.gen_range(0..self.width);
+            let y = rng.gen_range(0..self.height);
+            let weight = self.weights[(y * self.width + x) as usize];
+            if rng.gen_range(0. ..self.max) < weight {
+                let jitter = vec2(rng.gen_range(0. ..1.), rng.gen_range(0. ..1.));
+                return origin + (vec2(x as f32, y as f32) + jitter) * scale;
+            }
+        }
+    }
+}
+
+/// What a layout needs to know about the whole population being placed.
+/// `Layout::Image` without a density map falls back to `Layout::Uniform`.
+#[derive(Clone, Copy, Debug)]
+pub struct Placement<'a> {
+    pub layout: Layout,
+    /// Number of particles being placed
+    pub count: usize,
+    pub num_species: usize,
+    pub density: Option<&'a DensityMap>,
+}
+
+impl Placement<'_> {
+    /// Position for the `index`th particle, which belongs to `species`.
+    pub fn place(&self, index: usize, species: usize, rng: &mut ChaCha8Rng) -> Vec2 {
+        let Placement {
+            layout,
+            count,
+            num_species,
+            density,
+        } = *self;
+        let uniform = |rng: &mut ChaCha8Rng| vec2(rng.gen_range(0. ..1.), rng.gen_range(0. ..1.));
+        // Uniform over a disc of the given radius, or a slice of it
+        let in_disc = |rng: &mut ChaCha8Rng, inner: f32, outer: f32, from: f32, to: f32| {
+            let r = rng.gen_range(inner * inner..outer * outer).sqrt();
+            let angle = rng.gen_range(from..to);
+            CENTRE + vec2(angle.cos(), angle.sin()) * r
+        };
+        let slice = TAU / num_species as f32;
+        match layout {
+            Layout::Uniform => uniform(rng),
+            Layout::Disc => in_disc(rng, 0., DISC_RADIUS, 0., TAU),
+            Layout::Ring => in_disc(rng, DISC_RADIUS - RING_WIDTH, DISC_RADIUS, 0., TAU),
+            Layout::Blobs => {
+                let angle = species as f32 * slice;
+                let centre = CENTRE + vec2(angle.cos(), angle.sin()) * BLOB_CIRCLE;
+                // Box-Muller
+                let u: f32 = rng.gen_range(f32::EPSILON..1.);
+                let v: f32 = rng.gen_range(0. ..TAU);
+                centre + vec2(v.cos(), v.sin()) * (-2. * u.ln()).sqrt() * BLOB_SIGMA
+            }
+            Layout::Stripes => {
+                let width = 1. / num_species as f32;
+                vec2(
+                    (species as f32 + rng.gen_range(0. ..1.)) * width,
+                    rng.gen_range(0. ..1.),
+                )
+            }
+            Layout::Sectors => {
+                let from = species as f32 * slice;
+                in_disc(rng, 0., DISC_RADIUS, from, from + slice)
+            }
+            Layout::Grid => {
+                let side = (count as f32).sqrt().ceil().max(1.) as usize;
+                let cell = vec2((index % side) as f32, (index / side) as f32);
+                (cell + 0.5) / side as f32
+            }
+            Layout::Image => match density {
+                Some(density) if !density.is_empty() => density.sample(rng),
+                _ => uniform(rng),
+            },
+        }
+    }
+}
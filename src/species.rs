@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Params;
+
+/// Per-species physics. Unset values fall back to the global `Params`, so a
+/// default `Species` behaves exactly like the homogeneous simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Species {
+    /// How far this species can see other particles
+    pub radius: Option<f32>,
+    /// Repulsion distance, as a fraction of `radius`
+    pub beta: Option<f32>,
+    /// Inertia: forces on this species are divided by its mass
+    pub mass: f32,
+    pub friction_half_life: Option<f32>,
+    /// Relative share of the initial population
+    pub proportion: f32,
+}
+
+impl Default for Species {
+    fn default() -> Self {
+        Species {
+            radius: None,
+            beta: None,
+            mass: 1.,
+            friction_half_life: None,
+            proportion: 1.,
+        }
+    }
+}
+
+/// A species' parameters with the global fallbacks filled in, ready for use
+/// in the force loop.
+#[derive(Clone, Copy, Debug)]
+pub struct ResolvedSpecies {
+    pub radius: f32,
+    pub beta: f32,
+    pub mass: f32,
+    pub friction_half_life: f32,
+}
+
+impl ResolvedSpecies {
+    /// How much velocity decays over `dt`.
+    pub fn friction_factor(&self, dt: f32) -> f32 {
+        0.5_f32.powf(dt / self.friction_half_life)
+    }
+}
+
+impl Species {
+    pub fn resolve(&self, params: &Params) -> ResolvedSpecies {
+        ResolvedSpecies {
+            radius: self.radius.unwrap_or(params.max_radius),
+            beta: self.beta.unwrap_or(params.beta),
+            mass: self.mass,
+            friction_half_life: self.friction_half_life.unwrap_or(params.friction_half_life),
+        }
+    }
+
+    /// Describes the first out-of-range value, if any.
+    pub fn check(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f32| {
+            if value > 0. && value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("{} must be a positive number, got {}", name, value))
+            }
+        };
+        if let Some(radius) = self.radius {
+            positive("radius", radius)?;
+        }
+        if let Some(beta) = self.beta {
+            if !(beta > 0. && beta < 1.) {
+                return Err(format!(
+                    "beta must be between 0 and 1 (exclusive), got {}",
+                    beta
+                ));
+            }
+        }
+        positive("mass", self.mass)?;
+        if let Some(half_life) = self.friction_half_life {
+            positive("friction_half_life", half_life)?;
+        }
+        if !(self.proportion >= 0. && self.proportion.is_finite()) {
+            return Err(format!(
+                "proportion must be zero or positive, got {}",
+                self.proportion
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks every species and that at least one of them would be spawned.
+pub fn check_all(species: &[Species]) -> Result<(), String> {
+    for (i, s) in species.iter().enumerate() {
+        s.check().map_err(|e| format!("species {}: {}", i, e))?;
+    }
+    if !species.is_empty() && species.iter().all(|s| s.proportion == 0.) {
+        return Err("at least one species needs a proportion above zero".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+/// Converts variable frame times into a whole number of fixed-size simulation
+/// steps, so trajectories depend only on the seed and config, never on the
+/// frame rate.
+pub struct FixedStepper {
+    pub steps_per_second: f32,
+    /// Upper bound on steps per frame at normal speed, so a slow frame cannot
+    /// snowball
+    pub max_steps_per_frame: usize,
+    /// Playback rate: 2 runs twice as many steps per second, 0.25 is slow
+    /// motion
+    pub speed: f32,
+    pub paused: bool,
+    accumulator: f32,
+    pending_steps: usize,
+}
+
+const MIN_SPEED: f32 = 1. / 16.;
+const MAX_SPEED: f32 = 16.;
+
+impl FixedStepper {
+    pub fn new(steps_per_second: f32) -> Self {
+        FixedStepper {
+            steps_per_second,
+            max_steps_per_frame: 8,
+            speed: 1.,
+            paused: false,
+            accumulator: 0.,
+            pending_steps: 0,
+        }
+    }
+
+    /// Number of steps to run for a frame that took `frame_time` seconds.
+    pub fn steps_for(&mut self, frame_time: f32) -> usize {
+        if self.paused {
+            self.accumulator = 0.;
+            return std::mem::take(&mut self.pending_steps);
+        }
+        self.accumulator += frame_time * self.steps_per_second * self.speed;
+        let steps = self.accumulator.floor();
+        self.accumulator -= steps;
+        let steps = steps as usize;
+        // Speeding up raises the cap with it, so it still adds substeps
+        let max_steps = (self.max_steps_per_frame as f32 * self.speed.max(1.)).ceil() as usize;
+        if steps > max_steps {
+            // Drop the backlog rather than trying to catch up
+            self.accumulator = 0.;
+            max_steps
+        } else {
+            steps
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending_steps = 0;
+    }
+
+    /// Pause if running, and run exactly one step on the next frame.
+    pub fn single_step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.).max(MIN_SPEED);
+    }
+}
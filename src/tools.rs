@@ -0,0 +1,132 @@
+use macroquad::prelude::*;
+use particle_life::{FieldMode, ForceField, Simulation};
+
+use crate::camera::Camera;
+
+const MIN_RADIUS: f32 = 5.;
+const MAX_RADIUS: f32 = 300.;
+const RADIUS_STEP: f32 = 1.25;
+// Particles added per frame while spawning
+const SPAWN_RATE: usize = 10;
+
+// What the left mouse button does
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Tool {
+    // The configured force field, centred on the cursor
+    Field,
+    // Pull only the particles under the brush
+    LocalAttract,
+    Repel,
+    Spawn,
+    Erase,
+}
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Field => "field",
+            Tool::LocalAttract => "local attract",
+            Tool::Repel => "repel",
+            Tool::Spawn => "spawn",
+            Tool::Erase => "erase",
+        }
+    }
+
+    fn has_radius(self) -> bool {
+        self != Tool::Field
+    }
+}
+
+// The selected tool with its settings. The radius is in screen pixels, so the
+// brush covers the same part of the view at any zoom
+pub struct Brush {
+    pub tool: Tool,
+    pub species: usize,
+    pub radius: f32,
+}
+
+impl Brush {
+    pub fn new() -> Self {
+        Brush {
+            tool: Tool::Field,
+            species: 0,
+            radius: 40.,
+        }
+    }
+
+    pub fn handle_keys(&mut self, num_species: usize) {
+        let tools = [
+            (KeyCode::Key1, Tool::Field),
+            (KeyCode::Key2, Tool::LocalAttract),
+            (KeyCode::Key3, Tool::Repel),
+            (KeyCode::Key4, Tool::Spawn),
+            (KeyCode::Key5, Tool::Erase),
+        ];
+        for (key, tool) in tools {
+            if is_key_pressed(key) {
+                self.tool = tool;
+            }
+        }
+        if is_key_pressed(KeyCode::Q) {
+            self.species = (self.species + 1) % num_species;
+        }
+        // Keep a valid species if the simulation shrank
+        self.species = self.species.min(num_species - 1);
+        if is_key_pressed(KeyCode::Equal) {
+            self.radius = (self.radius * RADIUS_STEP).min(MAX_RADIUS);
+        }
+        if is_key_pressed(KeyCode::Minus) {
+            self.radius = (self.radius / RADIUS_STEP).max(MIN_RADIUS);
+        }
+    }
+
+    // The force the current tool applies during the physics step, if any
+    fn field(&self, sim: &Simulation, camera: &Camera) -> Option<ForceField> {
+        let brush = |mode| ForceField {
+            mode,
+            radius: Some(camera.world_length(self.radius)),
+            ..sim.params.field
+        };
+        match self.tool {
+            Tool::Field => Some(sim.params.field),
+            Tool::LocalAttract => Some(brush(FieldMode::Attract)),
+            Tool::Repel => Some(brush(FieldMode::Repel)),
+            Tool::Spawn | Tool::Erase => None,
+        }
+    }
+
+    // Sets up the force field for this frame's steps, and spawns or erases
+    // while the button is held
+    pub fn apply(&self, sim: &mut Simulation, target: Option<Vec2>, camera: &Camera) {
+        sim.active_field = target.and_then(|t| self.field(sim, camera).map(|f| (t, f)));
+        let Some(target) = target else {
+            return;
+        };
+        let radius = camera.world_length(self.radius);
+        match self.tool {
+            Tool::Spawn => sim.spawn_within(target, radius, self.species, SPAWN_RATE),
+            Tool::Erase => {
+                sim.erase_within(target, radius);
+            }
+            Tool::Field | Tool::LocalAttract | Tool::Repel => {}
+        }
+    }
+
+    // Brush outline at the cursor and the current tool next to the playback
+    // state
+    pub fn draw(&self, field: &ForceField, colors: &[Color]) {
+        let color = match self.tool {
+            Tool::Spawn => colors[self.species],
+            _ => GRAY,
+        };
+        if self.tool.has_radius() {
+            let (x, y) = mouse_position();
+            draw_circle_lines(x, y, self.radius, 1., color);
+        }
+        let label = match self.tool {
+            Tool::Field => format!("field: {:?}", field.mode).to_lowercase(),
+            tool => tool.name().to_string(),
+        };
+        draw_text(&label, 300., 30., 30., color);
+    }
+}
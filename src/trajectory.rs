@@ -0,0 +1,307 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::simulation::Particle;
+
+/// Trajectory file layouts, picked from the output path: `.csv` for one row
+/// per particle per step, anything else for the binary columnar format.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TrajectoryFormat {
+    Csv,
+    Binary,
+}
+
+impl TrajectoryFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => TrajectoryFormat::Csv,
+            _ => TrajectoryFormat::Binary,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "step,id,species,x,y,vx,vy";
+
+// The binary layout is a magic number and version, followed by one block per
+// recorded step: the step (u64) and particle count (u32), then each column in
+// turn (id, species, x, y, vx, vy), every value 4 bytes. All little-endian.
+const MAGIC: &[u8; 4] = b"PLTR";
+/// Bumped whenever the binary trajectory layout changes incompatibly.
+pub const TRAJECTORY_VERSION: u32 = 1;
+
+/// The state of every particle at one step, stored column by column.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrajectoryFrame {
+    pub step: u64,
+    pub ids: Vec<u32>,
+    pub species: Vec<u32>,
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub vx: Vec<f32>,
+    pub vy: Vec<f32>,
+}
+
+impl TrajectoryFrame {
+    pub fn capture(step: u64, population: &[Particle]) -> Self {
+        let mut frame = TrajectoryFrame {
+            step,
+            ..Default::default()
+        };
+        for p in population {
+            frame.push(p);
+        }
+        frame
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    fn push(&mut self, p: &Particle) {
+        self.ids.push(p.id);
+        self.species.push(p.color as u32);
+        self.x.push(p.position.x);
+        self.y.push(p.position.y);
+        self.vx.push(p.velocity.x);
+        self.vy.push(p.velocity.y);
+    }
+}
+
+#[derive(Debug)]
+pub enum TrajectoryError {
+    Io(PathBuf, std::io::Error),
+    Format(PathBuf, String),
+}
+
+impl fmt::Display for TrajectoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrajectoryError::Io(path, e) => {
+                write!(f, "could not access {}: {}", path.display(), e)
+            }
+            TrajectoryError::Format(path, msg) => {
+                write!(f, "{} is not a valid trajectory: {}", path.display(), msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TrajectoryError {}
+
+/// Streams frames to disk as they are produced, so long runs never hold the
+/// whole trajectory in memory.
+pub struct TrajectoryWriter {
+    path: PathBuf,
+    format: TrajectoryFormat,
+    out: BufWriter<File>,
+    frames: usize,
+}
+
+impl TrajectoryWriter {
+    pub fn create(path: &Path) -> Result<Self, TrajectoryError> {
+        let io_err = |e| TrajectoryError::Io(path.to_path_buf(), e);
+        let format = TrajectoryFormat::from_path(path);
+        let mut out = BufWriter::new(File::create(path).map_err(io_err)?);
+        match format {
+            TrajectoryFormat::Csv => writeln!(out, "{}", CSV_HEADER),
+            TrajectoryFormat::Binary => out
+                .write_all(MAGIC)
+                .and_then(|()| out.write_all(&TRAJECTORY_VERSION.to_le_bytes())),
+        }
+        .map_err(io_err)?;
+        Ok(TrajectoryWriter {
+            path: path.to_path_buf(),
+            format,
+            out,
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Append the population as it stands after `step` steps.
+    pub fn write(&mut self, step: u64, population: &[Particle]) -> Result<(), TrajectoryError> {
+        self.write_frame(&TrajectoryFrame::capture(step, population))
+    }
+
+    pub fn write_frame(&mut self, frame: &TrajectoryFrame) -> Result<(), TrajectoryError> {
+        let result = match self.format {
+            TrajectoryFormat::Csv => write_csv(&mut self.out, frame),
+            TrajectoryFormat::Binary => write_binary(&mut self.out, frame),
+        };
+        result.map_err(|e| TrajectoryError::Io(self.path.clone(), e))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), TrajectoryError> {
+        self.out
+            .flush()
+            .map_err(|e| TrajectoryError::Io(self.path, e))
+    }
+}
+
+fn write_csv(out: &mut impl Write, frame: &TrajectoryFrame) -> std::io::Result<()> {
+    // Display for floats prints the shortest text that parses back exactly
+    for i in 0..frame.len() {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            frame.step,
+            frame.ids[i],
+            frame.species[i],
+            frame.x[i],
+            frame.y[i],
+            frame.vx[i],
+            frame.vy[i]
+        )?;
+    }
+    Ok(())
+}
+
+fn write_binary(out: &mut impl Write, frame: &TrajectoryFrame) -> std::io::Result<()> {
+    out.write_all(&frame.step.to_le_bytes())?;
+    out.write_all(&(frame.len() as u32).to_le_bytes())?;
+    for id in &frame.ids {
+        out.write_all(&id.to_le_bytes())?;
+    }
+    for species in &frame.species {
+        out.write_all(&species.to_le_bytes())?;
+    }
+    for column in [&frame.x, &frame.y, &frame.vx, &frame.vy] {
+        for value in column {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Load every frame of a trajectory written by `TrajectoryWriter`.
+pub fn read(path: &Path) -> Result<Vec<TrajectoryFrame>, TrajectoryError> {
+    let file = File::open(path).map_err(|e| TrajectoryError::Io(path.to_path_buf(), e))?;
+    let reader = BufReader::new(file);
+    match TrajectoryFormat::from_path(path) {
+        TrajectoryFormat::Csv => read_csv(path, reader),
+        TrajectoryFormat::Binary => read_binary(path, reader),
+    }
+}
+
+fn read_csv(path: &Path, reader: impl BufRead) -> Result<Vec<TrajectoryFrame>, TrajectoryError> {
+    let format_err = |line: usize, msg: &str| {
+        TrajectoryError::Format(path.to_path_buf(), format!("line {}: {}", line, msg))
+    };
+    let mut frames: Vec<TrajectoryFrame> = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| TrajectoryError::Io(path.to_path_buf(), e))?;
+        if i == 0 {
+            if line.trim() != CSV_HEADER {
+                return Err(format_err(1, &format!("expected header {:?}", CSV_HEADER)));
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != 7 {
+            return Err(format_err(i + 1, "expected 7 fields"));
+        }
+        let bad = || format_err(i + 1, "could not parse a number");
+        let step: u64 = fields[0].parse().map_err(|_| bad())?;
+        if frames.last().is_none_or(|f| f.step != step) {
+            frames.push(TrajectoryFrame {
+                step,
+                ..Default::default()
+            });
+        }
+        let frame = frames.last_mut().unwrap();
+        frame.ids.push(fields[1].parse().map_err(|_| bad())?);
+        frame.species.push(fields[2].parse().map_err(|_| bad())?);
+        let mut floats = [0.; 4];
+        for (value, text) in floats.iter_mut().zip(&fields[3..]) {
+            *value = text.parse().map_err(|_| bad())?;
+        }
+        frame.x.push(floats[0]);
+        frame.y.push(floats[1]);
+        frame.vx.push(floats[2]);
+        frame.vy.push(floats[3]);
+    }
+    Ok(frames)
+}
+
+fn read_binary(
+    path: &Path,
+    mut reader: impl Read,
+) -> Result<Vec<TrajectoryFrame>, TrajectoryError> {
+    let io_err = |e: std::io::Error| match e.kind() {
+        ErrorKind::UnexpectedEof => {
+            TrajectoryError::Format(path.to_path_buf(), "file ends mid-frame".to_string())
+        }
+        _ => TrajectoryError::Io(path.to_path_buf(), e),
+    };
+    let mut header = [0; 8];
+    reader.read_exact(&mut header).map_err(io_err)?;
+    if &header[..4] != MAGIC {
+        return Err(TrajectoryError::Format(
+            path.to_path_buf(),
+            "missing trajectory header".to_string(),
+        ));
+    }
+    let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+    if version != TRAJECTORY_VERSION {
+        return Err(TrajectoryError::Format(
+            path.to_path_buf(),
+            format!(
+                "version {}, but only version {} is supported",
+                version, TRAJECTORY_VERSION
+            ),
+        ));
+    }
+    let mut frames = Vec::new();
+    loop {
+        let mut step = [0; 8];
+        // A clean end of file is only allowed between frames
+        if reader.read(&mut step[..1]).map_err(io_err)? == 0 {
+            break;
+        }
+        reader.read_exact(&mut step[1..]).map_err(io_err)?;
+        let mut count = [0; 4];
+        reader.read_exact(&mut count).map_err(io_err)?;
+        let count = u32::from_le_bytes(count) as usize;
+        let mut column = || -> Result<Vec<[u8; 4]>, TrajectoryError> {
+            let mut bytes = vec![0; count * 4];
+            reader.read_exact(&mut bytes).map_err(io_err)?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|c| c.try_into().unwrap())
+                .collect())
+        };
+        let ids = column()?.into_iter().map(u32::from_le_bytes).collect();
+        let species = column()?.into_iter().map(u32::from_le_bytes).collect();
+        let mut floats = || -> Result<Vec<f32>, TrajectoryError> {
+            Ok(column()?.into_iter().map(f32::from_le_bytes).collect())
+        };
+        frames.push(TrajectoryFrame {
+            step: u64::from_le_bytes(step),
+            ids,
+            species,
+            x: floats()?,
+            y: floats()?,
+            vx: floats()?,
+            vy: floats()?,
+        });
+    }
+    Ok(frames)
+}
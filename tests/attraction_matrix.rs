@@ -0,0 +1,82 @@
+use glam::{vec2, Vec2};
+use particle_life::simulation::update_population;
+use particle_life::{AttractionMatrix, KernelKind, Params, Particle, Simulation};
+
+const SPECIES: usize = 5;
+
+#[test]
+fn every_pair_round_trips() {
+    let mut matrix = AttractionMatrix::zeros(SPECIES);
+    for from in 0..SPECIES {
+        for to in 0..SPECIES {
+            matrix.set(from, to, (from * 10 + to) as f32);
+        }
+    }
+    for from in 0..SPECIES {
+        for to in 0..SPECIES {
+            assert_eq!(matrix.get(from, to), (from * 10 + to) as f32);
+        }
+    }
+}
+
+#[test]
+fn from_values_is_row_major() {
+    let matrix = AttractionMatrix::from_values(2, vec![1., 2., 3., 4.]).unwrap();
+    assert_eq!(matrix.get(0, 1), 2.);
+    assert_eq!(matrix.get(1, 0), 3.);
+}
+
+#[test]
+fn from_values_checks_dimensions() {
+    let err = AttractionMatrix::from_values(3, vec![0.; 8]).unwrap_err();
+    assert_eq!((err.size, err.len), (3, 8));
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn get_rejects_unknown_species() {
+    AttractionMatrix::zeros(SPECIES).get(0, SPECIES);
+}
+
+// With only (from, to) set, `from` must be pulled toward `to` while `to`
+// feels nothing but the centre pull.
+#[test]
+fn update_population_reads_the_right_pair() {
+    let params = Params {
+        num_particles: 2,
+        kernel: KernelKind::Linear,
+        ..Params::default()
+    };
+    let centre_pull = |p: Vec2| -(p - vec2(0.5, 0.5)) / 128.;
+    for from in 0..SPECIES {
+        for to in 0..SPECIES {
+            if from == to {
+                continue;
+            }
+            let mut matrix = AttractionMatrix::zeros(SPECIES);
+            matrix.set(from, to, 1.);
+            let a = vec2(0.45, 0.5);
+            let b = a + vec2(params.max_radius * 0.6, 0.);
+            let population = [
+                Particle {
+                    id: 0,
+                    color: from,
+                    position: a,
+                    velocity: Vec2::ZERO,
+                },
+                Particle {
+                    id: 1,
+                    color: to,
+                    position: b,
+                    velocity: Vec2::ZERO,
+                },
+            ];
+            let mut sim = Simulation::new(params, SPECIES);
+            sim.attractions = matrix;
+            sim.population = population.to_vec();
+            let next = update_population(&sim);
+            assert!(next[0].velocity.x > centre_pull(a).x, "{} -> {}", from, to);
+            assert_eq!(next[1].velocity, centre_pull(b), "{} -> {}", from, to);
+        }
+    }
+}
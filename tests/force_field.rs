@@ -0,0 +1,52 @@
+use glam::{vec2, Vec2};
+use particle_life::{FieldMode, ForceField, Params, Simulation};
+
+#[test]
+fn particle_on_the_centre_feels_nothing() {
+    for mode in [FieldMode::Attract, FieldMode::Repel, FieldMode::Vortex] {
+        let field = ForceField {
+            mode,
+            ..Default::default()
+        };
+        assert_eq!(field.acceleration(Vec2::ZERO), Vec2::ZERO);
+    }
+}
+
+#[test]
+fn modes_point_the_right_way_and_fall_off() {
+    let field = ForceField {
+        mode: FieldMode::Attract,
+        strength: 2.,
+        radius: Some(0.5),
+    };
+    let to_centre = vec2(0.25, 0.);
+    assert_eq!(field.acceleration(to_centre), vec2(1., 0.));
+    let repel = ForceField {
+        mode: FieldMode::Repel,
+        ..field
+    };
+    assert_eq!(repel.acceleration(to_centre), vec2(-1., 0.));
+    let vortex = ForceField {
+        mode: FieldMode::Vortex,
+        ..field
+    };
+    assert_eq!(vortex.acceleration(to_centre), vec2(0., 1.));
+    assert_eq!(field.acceleration(vec2(0.5, 0.)), Vec2::ZERO);
+}
+
+#[test]
+fn field_centred_on_a_particle_keeps_the_simulation_finite() {
+    let params = Params {
+        num_particles: 100,
+        ..Default::default()
+    };
+    let mut sim = Simulation::new(params, 3);
+    sim.active_field = Some((sim.population[0].position, ForceField::default()));
+    for _ in 0..10 {
+        sim.step();
+    }
+    assert!(sim
+        .population
+        .iter()
+        .all(|p| p.position.is_finite() && p.velocity.is_finite()));
+}
@@ -0,0 +1,66 @@
+use particle_life::{Boundary, NeighbourSearch, Params, Particle, Simulation};
+
+const SPECIES: usize = 5;
+const STEPS: usize = 20;
+
+fn run(boundary: Boundary, search: NeighbourSearch) -> Vec<Particle> {
+    let params = Params {
+        num_particles: 500,
+        boundary,
+        search,
+        ..Params::default()
+    };
+    let mut sim = Simulation::new(params, SPECIES);
+    for _ in 0..STEPS {
+        sim.step();
+    }
+    sim.population
+}
+
+// The spatial indexes only exist to speed up the brute-force search, so they
+// must reproduce it exactly, not just approximately.
+#[test]
+fn every_search_matches_brute_force() {
+    for boundary in [Boundary::CentrePull, Boundary::Wrap] {
+        let expected = run(boundary, NeighbourSearch::BruteForce);
+        for search in [NeighbourSearch::Grid, NeighbourSearch::KdTree] {
+            assert!(
+                run(boundary, search) == expected,
+                "{:?} differs from brute force under {:?}",
+                search,
+                boundary
+            );
+        }
+    }
+}
+
+// Switching to a wrapped world mid-run must not leave particles outside the
+// square, where the indexes would miss neighbours across the edge.
+#[test]
+fn switching_to_wrap_matches_brute_force() {
+    let switched = |search| {
+        let params = Params {
+            num_particles: 500,
+            boundary: Boundary::Open,
+            search,
+            ..Params::default()
+        };
+        let mut sim = Simulation::new(params, SPECIES);
+        for p in &mut sim.population {
+            p.position = p.position * 4. - 1.5;
+        }
+        sim.set_boundary(Boundary::Wrap);
+        for _ in 0..STEPS {
+            sim.step();
+        }
+        sim.population
+    };
+    let expected = switched(NeighbourSearch::BruteForce);
+    for search in [NeighbourSearch::Grid, NeighbourSearch::KdTree] {
+        assert!(
+            switched(search) == expected,
+            "{:?} differs from brute force",
+            search
+        );
+    }
+}
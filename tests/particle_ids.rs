@@ -0,0 +1,20 @@
+use glam::vec2;
+use particle_life::{Params, Simulation};
+
+// Removing the newest particle must not free its id for the next one added.
+#[test]
+fn ids_are_never_reused() {
+    let params = Params {
+        num_particles: 10,
+        ..Params::default()
+    };
+    let mut sim = Simulation::new(params, 3);
+    let last = sim.population.last_mut().unwrap();
+    assert_eq!(last.id, 9);
+    last.position = vec2(5., 5.);
+    assert_eq!(sim.erase_within(vec2(5., 5.), 0.01), 1);
+    sim.spawn_within(vec2(0.5, 0.5), 0.1, 0, 1);
+    assert_eq!(sim.population.last().unwrap().id, 10);
+    sim.set_particle_count(12);
+    assert_eq!(sim.population.last().unwrap().id, 12);
+}
@@ -0,0 +1,53 @@
+use particle_life::{Params, Simulation, Snapshot, SnapshotError};
+
+type Edit = fn(&mut Snapshot);
+
+fn load_edited(file_name: &str, edit: impl Fn(&mut Snapshot)) -> Result<Snapshot, SnapshotError> {
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), file_name));
+    let params = Params {
+        num_particles: 50,
+        ..Default::default()
+    };
+    let mut snapshot = Snapshot::capture(&Simulation::new(params, 3));
+    edit(&mut snapshot);
+    snapshot.save(&path).unwrap();
+    let loaded = Snapshot::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    loaded
+}
+
+#[test]
+fn resumes_exactly() {
+    let params = Params {
+        num_particles: 200,
+        ..Default::default()
+    };
+    let mut sim = Simulation::new(params, 3);
+    sim.step();
+    let mut resumed = Snapshot::capture(&sim).restore();
+    for _ in 0..5 {
+        sim.step();
+        resumed.step();
+    }
+    assert!(resumed.population == sim.population);
+}
+
+// Values a config would reject must not reach the simulation by way of a
+// hand-edited snapshot either.
+#[test]
+fn rejects_out_of_range_values() {
+    let edits: [(&str, Edit); 4] = [
+        ("proportion", |s| s.species[0].proportion = -1.),
+        ("beta", |s| s.params.beta = 0.),
+        ("time-step", |s| s.params.time_step = 0.),
+        ("mass", |s| s.species[1].mass = -2.),
+    ];
+    for (name, edit) in edits {
+        let file_name = format!("invalid-{}.json", name);
+        match load_edited(&file_name, edit) {
+            Err(SnapshotError::Invalid(..)) => {}
+            Err(e) => panic!("{}: wrong error {}", name, e),
+            Ok(_) => panic!("{}: loaded without complaint", name),
+        }
+    }
+}
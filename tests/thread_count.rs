@@ -0,0 +1,33 @@
+use particle_life::{Params, Particle, Simulation};
+
+fn run_with_threads(threads: usize) -> Vec<Particle> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        let params = Params {
+            num_particles: 500,
+            ..Params::default()
+        };
+        let mut sim = Simulation::new(params, 5);
+        for _ in 0..20 {
+            sim.step();
+        }
+        sim.population
+    })
+}
+
+// Work is split differently across threads, but every particle's force sum
+// must come out the same.
+#[test]
+fn results_do_not_depend_on_thread_count() {
+    let single = run_with_threads(1);
+    for threads in [2, 4] {
+        assert!(
+            run_with_threads(threads) == single,
+            "{} threads differ from one",
+            threads
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use particle_life::trajectory::{self, TrajectoryWriter};
+use particle_life::{Params, Simulation};
+
+fn round_trip(file_name: &str) {
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), file_name));
+    let params = Params {
+        num_particles: 200,
+        ..Default::default()
+    };
+    let mut sim = Simulation::new(params, 3);
+    let mut writer = TrajectoryWriter::create(&path).unwrap();
+    let mut expected = Vec::new();
+    for step in 0..3 {
+        writer.write(step, &sim.population).unwrap();
+        expected.push(sim.population.clone());
+        sim.step();
+    }
+    writer.finish().unwrap();
+
+    let frames = trajectory::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(frames.len(), expected.len());
+    for (step, (frame, population)) in frames.iter().zip(&expected).enumerate() {
+        assert_eq!(frame.step, step as u64);
+        assert_eq!(frame.len(), population.len());
+        for (i, p) in population.iter().enumerate() {
+            assert_eq!(frame.species[i] as usize, p.color);
+            assert_eq!([frame.x[i], frame.y[i]], p.position.to_array());
+            assert_eq!([frame.vx[i], frame.vy[i]], p.velocity.to_array());
+        }
+    }
+}
+
+#[test]
+fn csv_round_trips_exactly() {
+    round_trip("trajectory.csv");
+}
+
+#[test]
+fn binary_round_trips_exactly() {
+    round_trip("trajectory.bin");
+}